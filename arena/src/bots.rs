@@ -0,0 +1,242 @@
+use std::io;
+
+use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
+use tictac::{
+    engine::{whole_turn, EngineProcess},
+    AnyGame, Cell, Game, GameResult, Move,
+};
+
+/// Anything that can take turns in the arena
+pub trait Player {
+    fn name(&self) -> String;
+
+    fn new_game(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The whole turn to play for the player to move in `game`
+    fn turn(&mut self, game: &AnyGame) -> io::Result<Vec<Move>>;
+}
+
+/// Builds a player from its command line name: `first`, `random`, `greedy`,
+/// `minimax` or `minimax:<depth>`
+pub fn builtin(name: &str, seed: u64) -> Option<Box<dyn Player>> {
+    let rng = SmallRng::seed_from_u64(seed);
+    let (kind, arg) = name.split_once(':').unwrap_or((name, ""));
+    Some(match kind {
+        "first" => Box::new(First),
+        "random" => Box::new(Random(rng)),
+        "greedy" => Box::new(Greedy(rng)),
+        "minimax" => Box::new(Minimax {
+            depth: if arg.is_empty() { 4 } else { arg.parse().ok()? },
+            rng,
+        }),
+        _ => return None,
+    })
+}
+
+/// Plays the first legal move
+pub struct First;
+
+impl Player for First {
+    fn name(&self) -> String {
+        "first".to_owned()
+    }
+
+    fn turn(&mut self, game: &AnyGame) -> io::Result<Vec<Move>> {
+        Ok(whole_turn(game, |g| g.legal_moves().into_iter().next()))
+    }
+}
+
+/// Plays any legal move
+pub struct Random(SmallRng);
+
+impl Player for Random {
+    fn name(&self) -> String {
+        "random".to_owned()
+    }
+
+    fn turn(&mut self, game: &AnyGame) -> io::Result<Vec<Move>> {
+        let rng = &mut self.0;
+        Ok(whole_turn(game, |g| g.legal_moves().choose(rng).cloned()))
+    }
+}
+
+/// The same game with the other player to move, to see their threats
+fn as_opponent(game: &AnyGame) -> AnyGame {
+    let mut game = game.clone();
+    match &mut game {
+        AnyGame::Classic(game) => game.current_player = game.current_player.map(Cell::flip),
+        AnyGame::Numerical(game) => game.current_player = game.current_player.map(Cell::flip),
+    }
+    game
+}
+
+fn wins(game: &AnyGame, mv: &Move) -> bool {
+    let mover = game.current_player();
+    let mut next = game.clone();
+    next.apply(mv) && matches!(next.result(), GameResult::Won(winner) if Some(winner) == mover)
+}
+
+/// Wins when it can, blocks the opponent's winning cells, and otherwise
+/// plays at random
+pub struct Greedy(SmallRng);
+
+impl Player for Greedy {
+    fn name(&self) -> String {
+        "greedy".to_owned()
+    }
+
+    fn turn(&mut self, game: &AnyGame) -> io::Result<Vec<Move>> {
+        let rng = &mut self.0;
+        Ok(whole_turn(game, |g| {
+            let moves = g.legal_moves();
+            if let Some(mv) = moves.iter().find(|mv| wins(g, mv)) {
+                return Some(mv.clone());
+            }
+            let opponent = as_opponent(g);
+            let threats: Vec<_> = opponent
+                .legal_moves()
+                .into_iter()
+                .filter(|mv| wins(&opponent, mv))
+                .collect();
+            let blocks: Vec<_> = moves
+                .iter()
+                .filter(|mv| threats.iter().any(|t| (t.x, t.y) == (mv.x, mv.y)))
+                .collect();
+            match blocks.choose(rng) {
+                Some(mv) => Some((*mv).clone()),
+                None => moves.choose(rng).cloned(),
+            }
+        }))
+    }
+}
+
+/// Alpha-beta search over single stones, `depth` stones deep. Ties are
+/// broken at random so games don't all repeat.
+pub struct Minimax {
+    depth: usize,
+    rng: SmallRng,
+}
+
+const WIN: i32 = 1_000_000;
+
+impl Minimax {
+    /// Value of `game` for the player to move
+    fn search(game: &AnyGame, depth: usize, mut alpha: i32, beta: i32) -> i32 {
+        match game.result() {
+            // Quicker wins score higher
+            GameResult::Won(winner) => {
+                let value = WIN + depth as i32;
+                return if Some(winner) == game.current_player() {
+                    value
+                } else {
+                    -value
+                };
+            }
+            GameResult::Draw => return 0,
+            GameResult::Ongoing if depth == 0 => return 0,
+            GameResult::Ongoing => {}
+        }
+        let mover = game.current_player();
+        let mut best = -WIN * 2;
+        for mv in game.legal_moves() {
+            let mut next = game.clone();
+            if !next.apply(&mv) {
+                continue;
+            }
+            let value = if next.current_player() == mover {
+                Self::search(&next, depth - 1, alpha, beta)
+            } else {
+                -Self::search(&next, depth - 1, -beta, -alpha)
+            };
+            best = best.max(value);
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best
+    }
+}
+
+impl Player for Minimax {
+    fn name(&self) -> String {
+        format!("minimax:{}", self.depth)
+    }
+
+    fn turn(&mut self, game: &AnyGame) -> io::Result<Vec<Move>> {
+        let depth = self.depth.max(1);
+        let rng = &mut self.rng;
+        Ok(whole_turn(game, |g| {
+            let mover = g.current_player();
+            let mut moves = g.legal_moves();
+            moves.shuffle(rng);
+            let mut best: Option<(i32, Move)> = None;
+            for mv in moves {
+                let mut next = g.clone();
+                if !next.apply(&mv) {
+                    continue;
+                }
+                let value = if next.current_player() == mover {
+                    Self::search(&next, depth - 1, -WIN * 2, WIN * 2)
+                } else {
+                    -Self::search(&next, depth - 1, -WIN * 2, WIN * 2)
+                };
+                match &best {
+                    Some((v, _)) if *v >= value => {}
+                    _ => best = Some((value, mv)),
+                }
+            }
+            best.map(|(_, mv)| mv)
+        }))
+    }
+}
+
+/// An engine process speaking the protocol of `tictac::engine`
+pub struct External {
+    engine: EngineProcess,
+    movetime: Option<u64>,
+}
+
+impl External {
+    pub fn spawn(command: &str, movetime: Option<u64>) -> io::Result<Self> {
+        let words: Vec<String> = command.split_whitespace().map(str::to_owned).collect();
+        let (program, args) = words
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty engine command"))?;
+        Ok(Self {
+            engine: EngineProcess::spawn(program, args)?,
+            movetime,
+        })
+    }
+}
+
+impl Player for External {
+    fn name(&self) -> String {
+        self.engine.name.clone()
+    }
+
+    fn new_game(&mut self) -> io::Result<()> {
+        self.engine.new_game()
+    }
+
+    fn turn(&mut self, game: &AnyGame) -> io::Result<Vec<Move>> {
+        self.engine.best_move(game, self.movetime)
+    }
+}
+
+/// A few random stones to start from, so deterministic players don't play
+/// the same game over and over
+pub fn random_opening(game: &mut AnyGame, plies: usize, rng: &mut impl Rng) {
+    for _ in 0..plies {
+        if game.result() != GameResult::Ongoing {
+            return;
+        }
+        let moves = game.legal_moves();
+        if moves.is_empty() {
+            return;
+        }
+        game.apply(&moves[rng.gen_range(0..moves.len())]);
+    }
+}
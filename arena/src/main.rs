@@ -0,0 +1,321 @@
+//! Plays engines against each other without the server:
+//!
+//! ```text
+//! arena --bot greedy --bot minimax:3 --engine "./my-engine --fast" \
+//!     --size 3,4,5 --criteria 3,4 --games 50 [--movetime 100] \
+//!     [--openings 2] [--variant stones=1,2] [--seed 1] \
+//!     [--sprt 0,20 --alpha 0.05 --beta 0.05]
+//! ```
+//!
+//! Every player meets every other one on each board (criteria above the
+//! size are skipped), swapping sides after each game. `--games` is the number
+//! of games per pairing and board. With `--sprt elo0,elo1` and exactly two
+//! players the match stops early once the test decides whether the first
+//! player is `elo1` rather than `elo0` stronger.
+
+use std::{io, process::exit};
+
+use rand::{rngs::SmallRng, SeedableRng};
+use tictac::{notation, AnyGame, Cell, Game, GameResult};
+
+use bots::{random_opening, Player};
+use stats::{Score, Sprt, SprtTest};
+
+mod bots;
+mod stats;
+
+struct Options {
+    bots: Vec<String>,
+    engines: Vec<String>,
+    sizes: Vec<usize>,
+    criteria: Vec<usize>,
+    variant: String,
+    games: u32,
+    movetime: u64,
+    openings: usize,
+    seed: u64,
+    sprt: Option<SprtTest>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: arena [--bot first|random|greedy|minimax[:depth]]... [--engine <command>]... \
+         [--size <n,...>] [--criteria <n,...>] [--variant <variant>] [--games <n>] \
+         [--movetime <ms>] [--openings <plies>] [--seed <n>] \
+         [--sprt <elo0>,<elo1> [--alpha <a>] [--beta <b>]]"
+    );
+    exit(2)
+}
+
+fn list<T: std::str::FromStr>(s: &str) -> Vec<T> {
+    s.split(',')
+        .map(|n| n.trim().parse().unwrap_or_else(|_| usage()))
+        .collect()
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        bots: Vec::new(),
+        engines: Vec::new(),
+        sizes: vec![3],
+        criteria: vec![3],
+        variant: "classic".to_owned(),
+        games: 10,
+        movetime: 100,
+        openings: 0,
+        seed: 0,
+        sprt: None,
+    };
+    let (mut alpha, mut beta) = (0.05, 0.05);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        let mut number = || value().parse().unwrap_or_else(|_| usage());
+        match arg.as_str() {
+            "--bot" => options.bots.push(value()),
+            "--engine" => options.engines.push(value()),
+            "--size" => options.sizes = list(&value()),
+            "--criteria" => options.criteria = list(&value()),
+            "--variant" => options.variant = value(),
+            "--games" => options.games = number() as u32,
+            "--movetime" => options.movetime = number() as u64,
+            "--openings" => options.openings = number() as usize,
+            "--seed" => options.seed = number() as u64,
+            "--alpha" => alpha = number(),
+            "--beta" => beta = number(),
+            "--sprt" => {
+                let elo: Vec<f64> = list(&value());
+                if elo.len() != 2 {
+                    usage()
+                }
+                options.sprt = Some(SprtTest {
+                    elo0: elo[0],
+                    elo1: elo[1],
+                    alpha: 0.0,
+                    beta: 0.0,
+                });
+            }
+            _ => usage(),
+        }
+    }
+    if let Some(sprt) = &mut options.sprt {
+        sprt.alpha = alpha;
+        sprt.beta = beta;
+    }
+    options
+}
+
+/// Plays one game, X moving first. A player that makes an illegal turn or
+/// whose engine fails loses the game.
+fn play<'a>(x: &'a mut dyn Player, o: &'a mut dyn Player, mut game: AnyGame) -> GameResult {
+    for player in [&mut *x, &mut *o] {
+        if let Err(e) = player.new_game() {
+            eprintln!("{}: {e}", player.name());
+        }
+    }
+    // Rolling variants can go on forever
+    let limit = game.size() * game.size() * 4;
+    for _ in 0..limit {
+        let mover = match (game.result(), game.current_player()) {
+            (GameResult::Ongoing, Some(mover)) => mover,
+            (result, _) => return result,
+        };
+        let player = match mover {
+            Cell::X => &mut *x,
+            Cell::O => &mut *o,
+        };
+        let turn = player.turn(&game).unwrap_or_else(|e: io::Error| {
+            eprintln!("{}: {e}", player.name());
+            Vec::new()
+        });
+        if !game.play_turn(&turn) {
+            eprintln!("{} forfeits with an illegal turn", player.name());
+            return GameResult::Won(mover.flip());
+        }
+    }
+    match game.result() {
+        GameResult::Ongoing => GameResult::Draw,
+        result => result,
+    }
+}
+
+fn main() {
+    let options = parse_options();
+    let mut players: Vec<Box<dyn Player>> = Vec::new();
+    for (i, name) in options.bots.iter().enumerate() {
+        let bot = bots::builtin(name, options.seed + i as u64).unwrap_or_else(|| usage());
+        players.push(bot);
+    }
+    for command in &options.engines {
+        match bots::External::spawn(command, Some(options.movetime)) {
+            Ok(engine) => players.push(Box::new(engine)),
+            Err(e) => {
+                eprintln!("could not start `{command}`: {e}");
+                exit(1)
+            }
+        }
+    }
+    if players.len() < 2 || (options.sprt.is_some() && players.len() != 2) {
+        eprintln!("the arena needs at least two players, and exactly two for SPRT");
+        exit(2)
+    }
+
+    let mut boards = Vec::new();
+    for &size in &options.sizes {
+        for &criteria in &options.criteria {
+            match notation::new_game(&options.variant, size, criteria) {
+                Ok(game) if criteria <= size && game.is_valid() => {
+                    boards.push((format!("{size}x{size}/{criteria}"), game))
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!("{e}");
+                    exit(2)
+                }
+            }
+        }
+    }
+    if boards.is_empty() {
+        eprintln!("no board fits the given sizes and criteria");
+        exit(2)
+    }
+
+    let n = players.len();
+    let mut scores = vec![vec![Score::default(); n]; n];
+    let mut rng = SmallRng::seed_from_u64(options.seed);
+    let mut decision = Sprt::Continue;
+    'games: for round in 0..options.games {
+        for (_, board) in &boards {
+            let mut start = board.clone();
+            random_opening(&mut start, options.openings, &mut rng);
+            for i in 0..n {
+                for j in i + 1..n {
+                    // Sides swap every round
+                    let (x, o) = if round % 2 == 0 { (i, j) } else { (j, i) };
+                    let (low, high) = players.split_at_mut(j);
+                    let (pi, pj) = (&mut *low[i], &mut *high[0]);
+                    let result = if x == i {
+                        play(pi, pj, start.clone())
+                    } else {
+                        play(pj, pi, start.clone())
+                    };
+                    let score = &mut scores[x][o];
+                    match result {
+                        GameResult::Won(Cell::X) => score.wins += 1,
+                        GameResult::Won(Cell::O) => score.losses += 1,
+                        _ => score.draws += 1,
+                    }
+                    if let Some(sprt) = &options.sprt {
+                        decision = sprt.status(&pairing(&scores, 0, 1));
+                        if decision != Sprt::Continue {
+                            break 'games;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let names: Vec<_> = players.iter().map(|p| p.name()).collect();
+    report(&names, &scores);
+    if let Some(sprt) = &options.sprt {
+        let score = pairing(&scores, 0, 1);
+        let (lower, upper) = sprt.bounds();
+        println!();
+        println!(
+            "SPRT elo0={} elo1={}: LLR {:.2} ({:.2}, {:.2}) after {} games: {}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.llr(&score),
+            lower,
+            upper,
+            score.games(),
+            match decision {
+                Sprt::Continue => "inconclusive".to_owned(),
+                Sprt::H0 => format!("H0 accepted, {} is not stronger", names[0]),
+                Sprt::H1 => format!("H1 accepted, {} is stronger", names[0]),
+            }
+        );
+    }
+}
+
+/// All games between `i` and `j`, from `i`'s point of view
+fn pairing(scores: &[Vec<Score>], i: usize, j: usize) -> Score {
+    let (a, b) = (scores[i][j], scores[j][i].flip());
+    Score {
+        wins: a.wins + b.wins,
+        draws: a.draws + b.draws,
+        losses: a.losses + b.losses,
+    }
+}
+
+fn report(names: &[String], scores: &[Vec<Score>]) {
+    let width = names.iter().map(|n| n.len()).max().unwrap_or(0).max(6);
+    let n = names.len();
+    println!(
+        "{:<w$}  {:<w$}  {:>5} {:>5} {:>5} {:>6} {:>14}",
+        "Player",
+        "Versus",
+        "W",
+        "D",
+        "L",
+        "Score",
+        "Elo",
+        w = width
+    );
+    let mut totals = vec![Score::default(); n];
+    for i in 0..n {
+        for j in 0..n {
+            if i == j {
+                continue;
+            }
+            let score = pairing(scores, i, j);
+            let total = &mut totals[i];
+            total.wins += score.wins;
+            total.draws += score.draws;
+            total.losses += score.losses;
+            if i < j {
+                let (elo, margin) = score.elo();
+                println!(
+                    "{:<w$}  {:<w$}  {:>5} {:>5} {:>5} {:>5.1}% {:>+7.0} ±{:<5.0}",
+                    names[i],
+                    names[j],
+                    score.wins,
+                    score.draws,
+                    score.losses,
+                    score.ratio() * 100.0,
+                    elo,
+                    margin,
+                    w = width
+                );
+            }
+        }
+    }
+    println!();
+    println!(
+        "{:<w$}  {:>5} {:>5} {:>5} {:>6} {:>14}",
+        "Standings",
+        "W",
+        "D",
+        "L",
+        "Score",
+        "Elo vs field",
+        w = width
+    );
+    let mut order: Vec<_> = (0..n).collect();
+    order.sort_by(|&a, &b| totals[b].ratio().total_cmp(&totals[a].ratio()));
+    for i in order {
+        let (elo, margin) = totals[i].elo();
+        println!(
+            "{:<w$}  {:>5} {:>5} {:>5} {:>5.1}% {:>+7.0} ±{:<5.0}",
+            names[i],
+            totals[i].wins,
+            totals[i].draws,
+            totals[i].losses,
+            totals[i].ratio() * 100.0,
+            elo,
+            margin,
+            w = width
+        );
+    }
+}
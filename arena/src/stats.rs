@@ -0,0 +1,153 @@
+/// Wins, draws and losses from one player's point of view
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Score {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, a draw counting half
+    pub fn ratio(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64
+    }
+
+    /// The other player's point of view
+    pub fn flip(self) -> Self {
+        Self {
+            wins: self.losses,
+            draws: self.draws,
+            losses: self.wins,
+        }
+    }
+
+    /// Variance of the points of a single game
+    fn variance(&self) -> f64 {
+        let n = self.games().max(1) as f64;
+        let mean = self.ratio();
+        (self.wins as f64 * (1.0 - mean).powi(2)
+            + self.draws as f64 * (0.5 - mean).powi(2)
+            + self.losses as f64 * mean.powi(2))
+            / n
+    }
+
+    /// Elo difference the score suggests, with the 95% error margin
+    pub fn elo(&self) -> (f64, f64) {
+        let n = self.games().max(1) as f64;
+        let margin = 1.96 * (self.variance() / n).sqrt();
+        let ratio = self.ratio();
+        let low = elo_of(ratio - margin);
+        let high = elo_of(ratio + margin);
+        (elo_of(ratio), (high - low) / 2.0)
+    }
+}
+
+/// Elo difference behind an expected score, clamped for perfect scores
+pub fn elo_of(score: f64) -> f64 {
+    let score = score.clamp(0.001, 0.999);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Expected score of a player `elo` points stronger
+pub fn score_of(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sprt {
+    /// Not enough evidence yet
+    Continue,
+    /// The player is not `elo1` stronger (H0 holds)
+    H0,
+    /// The player is `elo1` stronger (H1 holds)
+    H1,
+}
+
+/// Sequential probability ratio test of H0: the Elo difference is `elo0`
+/// against H1: it is `elo1`, with the usual normal approximation of the
+/// log-likelihood ratio
+pub struct SprtTest {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl SprtTest {
+    pub fn llr(&self, score: &Score) -> f64 {
+        let variance = score.variance();
+        if score.games() == 0 || variance == 0.0 {
+            return 0.0;
+        }
+        let (s0, s1) = (score_of(self.elo0), score_of(self.elo1));
+        let n = score.games() as f64;
+        n * (s1 - s0) * (2.0 * score.ratio() - s0 - s1) / (2.0 * variance)
+    }
+
+    /// `(lower, upper)` bounds of the log-likelihood ratio
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    pub fn status(&self, score: &Score) -> Sprt {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            Sprt::H1
+        } else if llr <= lower {
+            Sprt::H0
+        } else {
+            Sprt::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{elo_of, score_of, Score, Sprt, SprtTest};
+
+    #[test]
+    fn elo_matches_score() {
+        assert!(elo_of(0.5).abs() < 1e-9);
+        assert!((elo_of(score_of(200.0)) - 200.0).abs() < 1e-6);
+        let score = Score {
+            wins: 60,
+            draws: 20,
+            losses: 20,
+        };
+        let (elo, margin) = score.elo();
+        assert!((elo - elo_of(0.7)).abs() < 1e-9);
+        assert!(margin > 0.0 && margin < elo);
+        assert!((score.flip().elo().0 + elo).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sprt_stops_on_clear_results() {
+        let test = SprtTest {
+            elo0: 0.0,
+            elo1: 50.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+        let even = Score {
+            wins: 5,
+            draws: 2,
+            losses: 5,
+        };
+        assert_eq!(Sprt::Continue, test.status(&even));
+        let strong = Score {
+            wins: 300,
+            draws: 50,
+            losses: 150,
+        };
+        assert_eq!(Sprt::H1, test.status(&strong));
+        assert_eq!(Sprt::H0, test.status(&strong.flip()));
+    }
+}
@@ -1,12 +1,28 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20221201_000002_add_bots;
+mod m20221215_000003_add_sessions;
+mod m20221220_000004_add_email;
+mod m20221228_000005_add_totp;
+mod m20230105_000006_add_login_attempts;
+mod m20230112_000007_add_roles;
+mod m20230119_000008_add_moderation;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20221201_000002_add_bots::Migration),
+            Box::new(m20221215_000003_add_sessions::Migration),
+            Box::new(m20221220_000004_add_email::Migration),
+            Box::new(m20221228_000005_add_totp::Migration),
+            Box::new(m20230105_000006_add_login_attempts::Migration),
+            Box::new(m20230112_000007_add_roles::Migration),
+            Box::new(m20230119_000008_add_moderation::Migration),
+        ]
     }
 }
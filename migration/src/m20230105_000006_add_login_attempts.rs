@@ -0,0 +1,49 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginAttempt::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginAttempt::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LoginAttempt::Username).string().not_null())
+                    .col(ColumnDef::new(LoginAttempt::Ip).string())
+                    .col(ColumnDef::new(LoginAttempt::Outcome).string().not_null())
+                    .col(
+                        ColumnDef::new(LoginAttempt::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LoginAttempt::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum LoginAttempt {
+    Table,
+    Id,
+    Username,
+    Ip,
+    Outcome,
+    CreatedAt,
+}
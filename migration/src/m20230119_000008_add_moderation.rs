@@ -0,0 +1,132 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(ColumnDef::new(User::MutedUntil).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+        // No foreign keys on reports or the log: both outlive the accounts
+        // they mention
+        manager
+            .create_table(
+                Table::create()
+                    .table(Report::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Report::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Report::ReporterId).integer().not_null())
+                    .col(ColumnDef::new(Report::ReportedId).integer().not_null())
+                    .col(ColumnDef::new(Report::GameId).big_integer().not_null())
+                    .col(ColumnDef::new(Report::Reason).string().not_null())
+                    .col(ColumnDef::new(Report::Comment).string())
+                    .col(ColumnDef::new(Report::GameRecord).text())
+                    .col(
+                        ColumnDef::new(Report::Status)
+                            .string()
+                            .not_null()
+                            .default("open"),
+                    )
+                    .col(ColumnDef::new(Report::CreatedAt).big_integer().not_null())
+                    .col(ColumnDef::new(Report::HandledBy).integer())
+                    .col(ColumnDef::new(Report::HandledAt).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(ModerationLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ModerationLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ModerationLog::ModeratorId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ModerationLog::Action).string().not_null())
+                    .col(ColumnDef::new(ModerationLog::TargetId).integer())
+                    .col(ColumnDef::new(ModerationLog::GameId).big_integer())
+                    .col(ColumnDef::new(ModerationLog::ReportId).integer())
+                    .col(ColumnDef::new(ModerationLog::Detail).string())
+                    .col(
+                        ColumnDef::new(ModerationLog::CreatedAt)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ModerationLog::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Report::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::MutedUntil)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum User {
+    Table,
+    MutedUntil,
+}
+
+#[derive(Iden)]
+enum Report {
+    Table,
+    Id,
+    ReporterId,
+    ReportedId,
+    GameId,
+    Reason,
+    Comment,
+    GameRecord,
+    Status,
+    CreatedAt,
+    HandledBy,
+    HandledAt,
+}
+
+#[derive(Iden)]
+enum ModerationLog {
+    Table,
+    Id,
+    ModeratorId,
+    Action,
+    TargetId,
+    GameId,
+    ReportId,
+    Detail,
+    CreatedAt,
+}
@@ -1,12 +1,24 @@
 use rocket_okapi::openapi_get_routes;
 
+mod admin;
+mod board;
+mod bot;
+mod email;
 mod home;
 mod hub;
+mod moderation;
+mod two_factor;
 mod user;
 
 pub fn routes() -> std::vec::Vec<rocket::Route> {
+    use admin::*;
+    use board::*;
+    use bot::*;
+    use email::*;
     use home::*;
     use hub::*;
+    use moderation::*;
+    use two_factor::*;
     use user::*;
     openapi_get_routes![
         homepage,
@@ -14,13 +26,54 @@ pub fn routes() -> std::vec::Vec<rocket::Route> {
         reset,
         register,
         login,
+        refresh,
+        logout,
+        get_sessions,
+        end_session,
         check_logged_in,
+        change_password,
+        rename,
+        delete_account,
+        set_email,
+        verify_email,
+        forgot_password,
+        reset_password,
+        login_second_factor,
+        enroll_totp,
+        enable_totp,
+        disable_totp,
+        new_totp_recovery_codes,
+        jwks,
         get_games,
         create_game,
         get_game_by_id,
         game_register,
         game_turn,
         game_reset,
-        game_delete
+        game_delete,
+        game_export,
+        game_import,
+        board_svg,
+        board_png,
+        replay_gif,
+        create_bot,
+        create_bot_key,
+        create_challenge,
+        get_challenges,
+        accept_challenge,
+        decline_challenge,
+        list_users,
+        ban_user,
+        unban_user,
+        mute_user,
+        unmute_user,
+        set_role,
+        abort_game,
+        delete_game,
+        server_stats,
+        report_player,
+        list_reports,
+        decide_report,
+        get_moderation_log
     ]
 }
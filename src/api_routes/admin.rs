@@ -0,0 +1,275 @@
+use chrono::Utc;
+use rocket::{delete, get, post, serde::json::Json, tokio::sync::RwLock, State};
+use rocket_okapi::openapi;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use tictac::api::{ReportStatus, RoleData, SanctionData, ServerStats, UserSummary};
+
+use super::{moderation::audit_entry, user::end_all_sessions};
+use crate::{
+    auth::{Admin, Moderator},
+    challenges::Challenges,
+    database::{report, session, user},
+    hub::{Hub, HubEntry},
+};
+
+fn summary(user: user::Model) -> UserSummary {
+    UserSummary {
+        id: user.id,
+        role: user.role(),
+        username: user.username,
+        is_bot: user.is_bot,
+        owner_id: user.owner_id,
+        banned_until: user.banned_until,
+        muted_until: user.muted_until,
+    }
+}
+
+/// Accounts by id, `limit` (at most 100) at a time
+#[openapi(tag = "Admin")]
+#[get("/admin/users?<offset>&<limit>&<banned>")]
+pub async fn list_users(
+    db: &State<DatabaseConnection>,
+    _moderator: Moderator,
+    offset: Option<u64>,
+    limit: Option<u64>,
+    banned: Option<bool>,
+) -> Option<Json<Vec<UserSummary>>> {
+    let mut query = user::Entity::find().order_by_asc(user::Column::Id);
+    if banned.unwrap_or(false) {
+        query = query.filter(user::Column::BannedUntil.gt(Utc::now().timestamp()));
+    }
+    let users = query
+        .offset(offset.unwrap_or(0))
+        .limit(limit.unwrap_or(50).min(100))
+        .all(&**db)
+        .await
+        .ok()?;
+    Some(Json(users.into_iter().map(summary).collect()))
+}
+
+#[derive(Clone, Copy)]
+enum Sanction {
+    Ban,
+    Mute,
+}
+
+/// An account the moderator outranks, as only those can be sanctioned
+async fn outranked(db: &DatabaseConnection, moderator: &Moderator, id: i32) -> Option<user::Model> {
+    let target = user::Entity::find_by_id(id).one(db).await.ok()??;
+    (target.role() < moderator.user.role()).then_some(target)
+}
+
+/// Hands out a sanction, or with `data` of `None` lifts it, and logs it
+async fn sanction(
+    db: &DatabaseConnection,
+    moderator: &Moderator,
+    id: i32,
+    kind: Sanction,
+    data: Option<&SanctionData>,
+) -> Option<user::Model> {
+    let target = outranked(db, moderator, id).await?;
+    let until = data.map(|data| match data.days {
+        Some(days) => Utc::now().timestamp() + days as i64 * 24 * 60 * 60,
+        None => i64::MAX,
+    });
+    let action = match (kind, data.is_some()) {
+        (Sanction::Ban, true) => "ban",
+        (Sanction::Ban, false) => "unban",
+        (Sanction::Mute, true) => "mute",
+        (Sanction::Mute, false) => "unmute",
+    };
+    let detail = data.map(|data| {
+        let length = match data.days {
+            Some(days) => format!("{days} days"),
+            None => "until lifted".to_owned(),
+        };
+        match &data.reason {
+            Some(reason) => format!("{length}: {reason}"),
+            None => length,
+        }
+    });
+    let txn = db.begin().await.ok()?;
+    let mut entry = audit_entry(&moderator.user, action);
+    entry.target_id = Set(Some(target.id));
+    entry.detail = Set(detail);
+    entry.insert(&txn).await.ok()?;
+    let mut account: user::ActiveModel = target.into();
+    match kind {
+        Sanction::Ban => account.banned_until = Set(until),
+        Sanction::Mute => account.muted_until = Set(until),
+    }
+    let target = account.update(&txn).await.ok()?;
+    txn.commit().await.ok()?;
+    Some(target)
+}
+
+/// Bans an account for some days, or until lifted, and ends its sessions.
+/// Bots of a banned account are shut out as well. Only accounts with a lower
+/// role can be banned.
+#[openapi(tag = "Admin")]
+#[post("/admin/users/<id>/ban", data = "<data>")]
+pub async fn ban_user(
+    db: &State<DatabaseConnection>,
+    challenges: &State<Challenges>,
+    moderator: Moderator,
+    id: i32,
+    data: Json<SanctionData>,
+) -> Option<Json<UserSummary>> {
+    let target = sanction(db, &moderator, id, Sanction::Ban, Some(&data.0)).await?;
+    end_all_sessions(db, target.id).await?;
+    challenges.rename(&target.username, None).await;
+    Some(Json(summary(target)))
+}
+
+#[openapi(tag = "Admin")]
+#[delete("/admin/users/<id>/ban")]
+pub async fn unban_user(
+    db: &State<DatabaseConnection>,
+    moderator: Moderator,
+    id: i32,
+) -> Option<Json<UserSummary>> {
+    let target = sanction(db, &moderator, id, Sanction::Ban, None).await?;
+    Some(Json(summary(target)))
+}
+
+/// Mutes an account for some days, or until lifted. Muted accounts keep
+/// playing, but can't send challenges or reports.
+#[openapi(tag = "Admin")]
+#[post("/admin/users/<id>/mute", data = "<data>")]
+pub async fn mute_user(
+    db: &State<DatabaseConnection>,
+    moderator: Moderator,
+    id: i32,
+    data: Json<SanctionData>,
+) -> Option<Json<UserSummary>> {
+    let target = sanction(db, &moderator, id, Sanction::Mute, Some(&data.0)).await?;
+    Some(Json(summary(target)))
+}
+
+#[openapi(tag = "Admin")]
+#[delete("/admin/users/<id>/mute")]
+pub async fn unmute_user(
+    db: &State<DatabaseConnection>,
+    moderator: Moderator,
+    id: i32,
+) -> Option<Json<UserSummary>> {
+    let target = sanction(db, &moderator, id, Sanction::Mute, None).await?;
+    Some(Json(summary(target)))
+}
+
+/// Hands out a role. Bots can't have one, and admins can't change their own,
+/// so there is always one left.
+#[openapi(tag = "Admin")]
+#[post("/admin/users/<id>/role", data = "<data>")]
+pub async fn set_role(
+    db: &State<DatabaseConnection>,
+    admin: Admin,
+    id: i32,
+    data: Json<RoleData>,
+) -> Option<Json<UserSummary>> {
+    let target = user::Entity::find_by_id(id).one(&**db).await.ok()??;
+    if target.is_bot || target.id == admin.user.id {
+        return None;
+    }
+    let txn = db.begin().await.ok()?;
+    let mut entry = audit_entry(&admin.user, "set_role");
+    entry.target_id = Set(Some(target.id));
+    entry.detail = Set(Some(data.role.as_str().to_owned()));
+    entry.insert(&txn).await.ok()?;
+    let mut account: user::ActiveModel = target.into();
+    account.role = Set(data.role.as_str().to_owned());
+    let target = account.update(&txn).await.ok()?;
+    txn.commit().await.ok()?;
+    Some(Json(summary(target)))
+}
+
+/// Ends a game where it stands, without a winner
+#[openapi(tag = "Admin")]
+#[post("/admin/games/<id>/abort")]
+pub async fn abort_game(
+    db: &State<DatabaseConnection>,
+    hub: &State<RwLock<Hub>>,
+    moderator: Moderator,
+    id: u64,
+) -> Option<Json<HubEntry>> {
+    let hub = hub.read().await;
+    let mut game = hub.entries.get(&id)?.write().await;
+    if !game.is_over() {
+        let mut entry = audit_entry(&moderator.user, "abort_game");
+        entry.game_id = Set(Some(id as i64));
+        entry.insert(&**db).await.ok()?;
+        game.aborted = true;
+    }
+    Some(Json(game.clone()))
+}
+
+/// Removes any game from the hub, finished or not
+#[openapi(tag = "Admin")]
+#[delete("/admin/games/<id>")]
+pub async fn delete_game(
+    db: &State<DatabaseConnection>,
+    hub: &State<RwLock<Hub>>,
+    admin: Admin,
+    id: u64,
+) -> Option<Json<bool>> {
+    let mut hub = hub.write().await;
+    if !hub.entries.contains_key(&id) {
+        return Some(Json(false));
+    }
+    let mut entry = audit_entry(&admin.user, "delete_game");
+    entry.game_id = Set(Some(id as i64));
+    entry.insert(&**db).await.ok()?;
+    hub.entries.remove(&id);
+    Some(Json(true))
+}
+
+#[openapi(tag = "Admin")]
+#[get("/admin/stats")]
+pub async fn server_stats(
+    db: &State<DatabaseConnection>,
+    hub: &State<RwLock<Hub>>,
+    challenges: &State<Challenges>,
+    _admin: Admin,
+) -> Option<Json<ServerStats>> {
+    let now = Utc::now().timestamp();
+    let accounts = |bots: bool| user::Entity::find().filter(user::Column::IsBot.eq(bots));
+    let users = accounts(false).count(&**db).await.ok()? as u64;
+    let bots = accounts(true).count(&**db).await.ok()? as u64;
+    let banned_users = user::Entity::find()
+        .filter(user::Column::BannedUntil.gt(now))
+        .count(&**db)
+        .await
+        .ok()? as u64;
+    let sessions = session::Entity::find()
+        .filter(session::Column::ExpiresAt.gt(now))
+        .count(&**db)
+        .await
+        .ok()? as u64;
+    let open_reports = report::Entity::find()
+        .filter(report::Column::Status.eq(ReportStatus::Open.as_str()))
+        .count(&**db)
+        .await
+        .ok()? as u64;
+    // Never hold the hub while waiting for the challenges
+    let open_challenges = challenges.entries.read().await.len();
+    let hub = hub.read().await;
+    let mut active_games = 0;
+    for entry in hub.entries.values() {
+        if !entry.read().await.is_over() {
+            active_games += 1;
+        }
+    }
+    Some(Json(ServerStats {
+        users,
+        bots,
+        banned_users,
+        sessions,
+        games: hub.entries.len(),
+        active_games,
+        open_challenges,
+        open_reports,
+    }))
+}
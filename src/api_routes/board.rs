@@ -0,0 +1,122 @@
+use rocket::{
+    get,
+    tokio::{sync::RwLock, task::spawn_blocking},
+    Responder, State,
+};
+use rocket_okapi::{
+    gen::OpenApiGenerator,
+    okapi::openapi3::{MediaType, Responses},
+    openapi,
+    response::OpenApiResponderInner,
+    util::add_content_response,
+};
+
+use crate::{
+    auth::Claims,
+    hub::{Hub, HubEntry},
+    render::{self, Renderer},
+};
+
+#[derive(Responder)]
+#[response(content_type = "image/svg+xml")]
+pub struct Svg(pub String);
+
+impl OpenApiResponderInner for Svg {
+    fn responses(_gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Responses::default();
+        add_content_response(&mut responses, 200, "image/svg+xml", MediaType::default())?;
+        Ok(responses)
+    }
+}
+
+#[derive(Responder)]
+#[response(content_type = "image/png")]
+pub struct Png(pub Vec<u8>);
+
+impl OpenApiResponderInner for Png {
+    fn responses(_gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Responses::default();
+        add_content_response(&mut responses, 200, "image/png", MediaType::default())?;
+        Ok(responses)
+    }
+}
+
+#[derive(Responder)]
+#[response(content_type = "image/gif")]
+pub struct Gif(pub Vec<u8>);
+
+impl OpenApiResponderInner for Gif {
+    fn responses(_gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Responses::default();
+        add_content_response(&mut responses, 200, "image/gif", MediaType::default())?;
+        Ok(responses)
+    }
+}
+
+async fn entry_svg(hub: &RwLock<Hub>, id: u64, claims: Option<Claims>) -> Option<String> {
+    let viewer = claims.map(|c| c.username);
+    let hub = hub.read().await;
+    let entry: HubEntry = hub
+        .entries
+        .get(&id)?
+        .read()
+        .await
+        .view_for(viewer.as_deref());
+    Some(render::board_svg(
+        &entry.game,
+        entry.x_player.as_deref(),
+        entry.o_player.as_deref(),
+    ))
+}
+
+#[openapi(tag = "Games")]
+#[get("/games/<id>/board.svg")]
+pub async fn board_svg(hub: &State<RwLock<Hub>>, id: u64, claims: Option<Claims>) -> Option<Svg> {
+    Some(Svg(entry_svg(hub, id, claims).await?))
+}
+
+#[openapi(tag = "Games")]
+#[get("/games/<id>/board.png")]
+pub async fn board_png(
+    hub: &State<RwLock<Hub>>,
+    renderer: &State<Renderer>,
+    id: u64,
+    claims: Option<Claims>,
+) -> Option<Png> {
+    let svg = entry_svg(hub, id, claims).await?;
+    let renderer = renderer.inner().clone();
+    let png = spawn_blocking(move || renderer.png(&svg)).await.ok()??;
+    Some(Png(png))
+}
+
+/// Animation of a finished game, `delay` milliseconds per move
+#[openapi(tag = "Games")]
+#[get("/games/<id>/replay.gif?<delay>")]
+pub async fn replay_gif(
+    hub: &State<RwLock<Hub>>,
+    renderer: &State<Renderer>,
+    id: u64,
+    delay: Option<u32>,
+) -> Option<Gif> {
+    let hub = hub.read().await;
+    let entry = hub.entries.get(&id)?.read().await.clone();
+    // Rendering takes a while, and the hub is not needed for it
+    drop(hub);
+    if !entry.is_over() {
+        return None;
+    }
+    // GIF frame delays are counted in hundredths of a second
+    let delay = (delay.unwrap_or(800) / 10).clamp(2, 1000) as u16;
+    let renderer = renderer.inner().clone();
+    let gif = spawn_blocking(move || {
+        renderer.replay_gif(
+            &entry.game,
+            entry.x_player.as_deref(),
+            entry.o_player.as_deref(),
+            delay,
+        )
+    })
+    .await
+    .ok()??;
+    Some(Gif(gif))
+}
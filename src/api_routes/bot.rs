@@ -0,0 +1,216 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use rocket::{
+    get, post,
+    serde::json::Json,
+    tokio::{sync::RwLock, time::timeout},
+    State,
+};
+use rocket_okapi::openapi;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use tictac::{
+    api::{BotCreated, BotData, Challenge, ChallengeData},
+    Game,
+};
+
+use crate::{
+    auth::{hash_token, may_play, new_api_key, Claims},
+    challenges::{BotConfig, Challenges},
+    hub::{Hub, DELETED_PLAYER},
+};
+
+async fn add_api_key(db: &DatabaseConnection, user_id: i32) -> Option<String> {
+    use crate::database::api_key;
+    let key = new_api_key();
+    let row = api_key::ActiveModel {
+        user_id: Set(user_id),
+        key_hash: Set(hash_token(&key)),
+        created_at: Set(Utc::now().timestamp()),
+        ..Default::default()
+    };
+    row.insert(db).await.ok()?;
+    Some(key)
+}
+
+/// Creates a bot account owned by the caller, with its first API key
+#[openapi(tag = "Bots")]
+#[post("/bots", data = "<data>")]
+pub async fn create_bot(
+    db: &State<DatabaseConnection>,
+    claims: Claims,
+    data: Json<BotData>,
+) -> Option<Json<BotCreated>> {
+    use crate::database::user;
+    if claims.bot || data.username.is_empty() || data.username == DELETED_PLAYER {
+        return None;
+    }
+    let owner = user::Entity::find()
+        .filter(user::Column::Username.eq(claims.username))
+        .one(&**db)
+        .await
+        .ok()??;
+    let bot = user::ActiveModel {
+        username: Set(data.username.clone()),
+        // Bots can't log in with a password, this only fills the column
+        password: Set(new_api_key()),
+        is_bot: Set(true),
+        owner_id: Set(Some(owner.id)),
+        ..Default::default()
+    };
+    let bot = bot.insert(&**db).await.ok()?;
+    let api_key = add_api_key(db, bot.id).await?;
+    Some(Json(BotCreated {
+        id: bot.id,
+        username: bot.username,
+        api_key,
+    }))
+}
+
+/// Another API key for a bot of the caller
+#[openapi(tag = "Bots")]
+#[post("/bots/<username>/keys")]
+pub async fn create_bot_key(
+    db: &State<DatabaseConnection>,
+    claims: Claims,
+    username: String,
+) -> Option<Json<String>> {
+    use crate::database::user;
+    let find = |name: String| {
+        user::Entity::find()
+            .filter(user::Column::Username.eq(name))
+            .one(&**db)
+    };
+    let owner = find(claims.username).await.ok()??;
+    let bot = find(username).await.ok()??;
+    if !bot.is_bot || bot.owner_id != Some(owner.id) {
+        return None;
+    }
+    Some(Json(add_api_key(db, bot.id).await?))
+}
+
+/// Invites `opponent` to a game with the given rules
+#[openapi(tag = "Bots")]
+#[post("/challenges", data = "<data>")]
+pub async fn create_challenge(
+    db: &State<DatabaseConnection>,
+    challenges: &State<Challenges>,
+    config: &State<BotConfig>,
+    claims: Claims,
+    data: Json<ChallengeData>,
+) -> Option<Json<u64>> {
+    use crate::database::user;
+    let sender = user::Entity::find()
+        .filter(user::Column::Username.eq(claims.username.clone()))
+        .one(&**db)
+        .await
+        .ok()??;
+    if sender.is_muted(Utc::now().timestamp()) {
+        return None;
+    }
+    let opponent = user::Entity::find()
+        .filter(user::Column::Username.eq(data.opponent.clone()))
+        .one(&**db)
+        .await
+        .ok()??;
+    let game = data.rules.to_any_game();
+    if opponent.username == claims.username || !game.is_playable() {
+        return None;
+    }
+    if data.rated && (claims.bot || opponent.is_bot) && !config.rated {
+        return None;
+    }
+    Some(Json(
+        challenges.add(claims.username, claims.bot, data.0).await,
+    ))
+}
+
+/// Challenges sent by or to the caller. With `wait`, holds the request for
+/// up to that many seconds until a challenge to the caller shows up, so bots
+/// can subscribe by asking again and again.
+#[openapi(tag = "Bots")]
+#[get("/challenges?<wait>")]
+pub async fn get_challenges(
+    challenges: &State<Challenges>,
+    claims: Claims,
+    wait: Option<u64>,
+) -> Json<Vec<Challenge>> {
+    let wait = Duration::from_secs(wait.unwrap_or(0).min(60));
+    let waiting = async {
+        loop {
+            let notified = challenges.new.notified();
+            let list = challenges.of(&claims.username).await;
+            if list.iter().any(|c| c.to == claims.username) {
+                return list;
+            }
+            notified.await;
+        }
+    };
+    match timeout(wait, waiting).await {
+        Ok(list) => Json(list),
+        Err(_) => Json(challenges.of(&claims.username).await),
+    }
+}
+
+/// Accepts a challenge to the caller, starting the game in the hub
+#[openapi(tag = "Bots")]
+#[post("/challenges/<id>/accept")]
+pub async fn accept_challenge(
+    db: &State<DatabaseConnection>,
+    hub: &State<RwLock<Hub>>,
+    challenges: &State<Challenges>,
+    config: &State<BotConfig>,
+    claims: Claims,
+    id: u64,
+) -> Option<Json<u64>> {
+    let challenge = challenges.entries.read().await.get(&id)?.clone();
+    if challenge.to != claims.username {
+        return None;
+    }
+    for player in [&challenge.from, &claims.username] {
+        if !may_play(db, player).await {
+            return None;
+        }
+    }
+    // The hub before the challenges, never the other way around
+    let mut hub = hub.write().await;
+    for (player, bot) in [
+        (&challenge.from, challenge.from_bot),
+        (&claims.username, claims.bot),
+    ] {
+        if bot && hub.active_games_of(player).await >= config.max_games {
+            return None;
+        }
+    }
+    // Gone if it was withdrawn or accepted in the meantime
+    let challenge = challenges.entries.write().await.remove(&id)?;
+    let game_id = hub.new_game(challenge.rules.to_any_game())?;
+    let mut entry = hub.entries.get(&game_id)?.write().await;
+    entry.set_player(challenge.side, challenge.from, challenge.from_bot);
+    entry.set_player(challenge.side.flip(), claims.username, claims.bot);
+    Some(Json(game_id))
+}
+
+/// Withdraws (as the challenger) or declines (as the opponent) a challenge
+#[openapi(tag = "Bots")]
+#[post("/challenges/<id>/decline")]
+pub async fn decline_challenge(
+    challenges: &State<Challenges>,
+    claims: Claims,
+    id: u64,
+) -> Json<bool> {
+    let mut entries = challenges.entries.write().await;
+    let allowed = matches!(
+        entries.get(&id),
+        Some(c) if c.from == claims.username || c.to == claims.username
+    );
+    if allowed {
+        entries.remove(&id);
+    }
+    Json(allowed)
+}
+
+/// Whether the caller may sit down in another game, given the bot limits
+pub async fn may_take_seat(hub: &Hub, config: &BotConfig, claims: &Claims) -> bool {
+    !claims.bot || hub.active_games_of(&claims.username).await < config.max_games
+}
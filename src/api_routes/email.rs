@@ -0,0 +1,184 @@
+use chrono::Utc;
+use rocket::{post, serde::json::Json, State};
+use rocket_okapi::openapi;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    Set,
+};
+use tictac::api::{EmailData, ForgotPassword, MailToken, PasswordReset};
+
+use crate::{
+    auth::{hash_token, new_mail_token, Claims},
+    database::{session, user, user_token},
+    mailer::{Mail, MailConfig, Mailer},
+};
+
+const VERIFY: &str = "verify";
+const RESET: &str = "reset";
+
+/// Hands out a token for `purpose`. Earlier ones for the same purpose stop
+/// working, so only the latest mail counts.
+async fn issue_token(
+    db: &DatabaseConnection,
+    user_id: i32,
+    purpose: &str,
+    email: Option<String>,
+    lifetime: i64,
+) -> Option<String> {
+    user_token::Entity::delete_many()
+        .filter(user_token::Column::UserId.eq(user_id))
+        .filter(user_token::Column::Purpose.eq(purpose))
+        .exec(db)
+        .await
+        .ok()?;
+    let token = new_mail_token();
+    let row = user_token::ActiveModel {
+        user_id: Set(user_id),
+        purpose: Set(purpose.to_owned()),
+        token_hash: Set(hash_token(&token)),
+        email: Set(email),
+        expires_at: Set(Utc::now().timestamp() + lifetime),
+        ..Default::default()
+    };
+    row.insert(db).await.ok()?;
+    Some(token)
+}
+
+/// Uses up a token. Of two requests with the same token only one gets it.
+async fn take_token(
+    db: &DatabaseConnection,
+    purpose: &str,
+    token: &str,
+) -> Option<user_token::Model> {
+    let row = user_token::Entity::find()
+        .filter(user_token::Column::TokenHash.eq(hash_token(token)))
+        .filter(user_token::Column::Purpose.eq(purpose))
+        .one(db)
+        .await
+        .ok()??;
+    let deleted = user_token::Entity::delete_many()
+        .filter(user_token::Column::Id.eq(row.id))
+        .exec(db)
+        .await
+        .ok()?;
+    (deleted.rows_affected == 1 && row.expires_at > Utc::now().timestamp()).then_some(row)
+}
+
+/// Sets the caller's email address, unverified until they follow the link
+/// mailed to it
+#[openapi(tag = "User control")]
+#[post("/user/email", data = "<data>")]
+pub async fn set_email(
+    db: &State<DatabaseConnection>,
+    mailer: &State<Box<dyn Mailer>>,
+    config: &State<MailConfig>,
+    claims: Claims,
+    data: Json<EmailData>,
+) -> Option<Json<bool>> {
+    if claims.bot || !data.email.contains('@') {
+        return None;
+    }
+    let user = user::Entity::find()
+        .filter(user::Column::Username.eq(claims.username))
+        .one(&**db)
+        .await
+        .ok()??;
+    let mut account: user::ActiveModel = user.into();
+    account.email = Set(Some(data.email.clone()));
+    account.email_verified = Set(false);
+    let user = account.update(&**db).await.ok()?;
+    let token = issue_token(db, user.id, VERIFY, user.email.clone(), 24 * 60 * 60).await?;
+    let mail = Mail {
+        to: data.email.clone(),
+        subject: "Verify your email address".to_owned(),
+        body: format!(
+            "Hi {},\n\nopen this link to verify your email address:\n\n{}/verify-email?token={token}\n\nThe link is valid for a day.",
+            user.username, config.base_url
+        ),
+    };
+    Some(Json(mailer.send(mail).await.is_ok()))
+}
+
+/// Verifies the address a verification mail was sent to
+#[openapi(tag = "User control")]
+#[post("/user/email/verify", data = "<data>")]
+pub async fn verify_email(db: &State<DatabaseConnection>, data: Json<MailToken>) -> Json<bool> {
+    let token = match take_token(db, VERIFY, &data.token).await {
+        Some(token) => token,
+        None => return Json(false),
+    };
+    // Only while the address is still the one the mail went to
+    let verified = user::Entity::update_many()
+        .col_expr(user::Column::EmailVerified, Expr::value(true))
+        .filter(user::Column::Id.eq(token.user_id))
+        .filter(user::Column::Email.eq(token.email))
+        .exec(&**db)
+        .await;
+    Json(matches!(verified, Ok(result) if result.rows_affected == 1))
+}
+
+/// Mails a password reset link to the verified address of an account, found
+/// by its address when the login has an `@` in it and by its name otherwise.
+/// The answer is the same whether or not there is such an account.
+#[openapi(tag = "User control")]
+#[post("/user/password/forgot", data = "<data>")]
+pub async fn forgot_password(
+    db: &State<DatabaseConnection>,
+    mailer: &State<Box<dyn Mailer>>,
+    config: &State<MailConfig>,
+    data: Json<ForgotPassword>,
+) -> Json<bool> {
+    // A name can look like somebody else's address, so never try both
+    let column = if data.login.contains('@') {
+        user::Column::Email
+    } else {
+        user::Column::Username
+    };
+    let user = user::Entity::find()
+        .filter(column.eq(data.login.clone()))
+        .filter(user::Column::EmailVerified.eq(true))
+        .filter(user::Column::IsBot.eq(false))
+        .one(&**db)
+        .await;
+    if let Ok(Some(user)) = user {
+        if let (Some(email), Some(token)) = (
+            user.email.clone(),
+            issue_token(db, user.id, RESET, None, 60 * 60).await,
+        ) {
+            let mail = Mail {
+                to: email,
+                subject: "Reset your password".to_owned(),
+                body: format!(
+                    "Hi {},\n\nopen this link to choose a new password:\n\n{}/reset-password?token={token}\n\nThe link is valid for an hour. If you didn't ask for it, ignore this mail.",
+                    user.username, config.base_url
+                ),
+            };
+            let _ = mailer.send(mail).await;
+        }
+    }
+    Json(true)
+}
+
+/// Sets a new password with the token from a reset mail. Every session of
+/// the account ends.
+#[openapi(tag = "User control")]
+#[post("/user/password/reset", data = "<data>")]
+pub async fn reset_password(
+    db: &State<DatabaseConnection>,
+    data: Json<PasswordReset>,
+) -> Option<Json<bool>> {
+    let token = take_token(db, RESET, &data.token).await?;
+    let user = user::Entity::find_by_id(token.user_id)
+        .one(&**db)
+        .await
+        .ok()??;
+    let mut account: user::ActiveModel = user.into();
+    account.password = Set(data.new_password.clone());
+    let user = account.update(&**db).await.ok()?;
+    session::Entity::delete_many()
+        .filter(session::Column::UserId.eq(user.id))
+        .exec(&**db)
+        .await
+        .ok()?;
+    Some(Json(true))
+}
@@ -1,8 +1,9 @@
 use rocket::{get, post, serde::json::Json, tokio::sync::Mutex, State};
-use rocket_okapi::{okapi::schemars::JsonSchema, openapi};
-use serde::Deserialize;
-
-use crate::tictac::TicTacToeGame;
+use rocket_okapi::openapi;
+use tictac::{
+    api::{ResetData, TurnData},
+    Game, GameKind, TicTacToeGame,
+};
 
 #[openapi(tag = "Homepage Tic-tac-toc game")]
 #[get("/")]
@@ -11,26 +12,17 @@ pub async fn homepage(game: &State<Mutex<TicTacToeGame>>) -> Json<TicTacToeGame>
     Json((*game).clone())
 }
 
-#[derive(Deserialize, JsonSchema)]
-pub struct TurnData {
-    pub x: usize,
-    pub y: usize,
-}
-
 #[openapi(tag = "Homepage Tic-tac-toc game")]
 #[post("/turn", data = "<data>")]
 pub async fn turn(game: &State<Mutex<TicTacToeGame>>, data: Json<TurnData>) -> Json<TicTacToeGame> {
     let mut game = game.lock().await;
-    (*game).turn(data.x, data.y);
+    (*game).play_turn(&data.moves());
     Json((*game).clone())
 }
 
-#[derive(Deserialize, JsonSchema)]
-pub struct ResetData {
-    pub size: usize,
-    pub criteria: usize,
-}
-
+/// Starts a new homepage game. The homepage only hosts classic boards, and
+/// everyone sees the same one, so other kinds and phantom games are refused
+/// and the old game stays.
 #[openapi(tag = "Homepage Tic-tac-toc game")]
 #[post("/reset", data = "<data>")]
 pub async fn reset(
@@ -38,6 +30,9 @@ pub async fn reset(
     data: Json<ResetData>,
 ) -> Json<TicTacToeGame> {
     let mut game = game.lock().await;
-    *game = TicTacToeGame::new(data.size, data.criteria);
+    let new_game = data.to_game();
+    if data.kind == GameKind::Classic && new_game.is_playable() && !new_game.phantom {
+        *game = new_game;
+    }
     Json((*game).clone())
 }
@@ -1,22 +1,36 @@
 use crate::{
-    auth::Claims,
-    hub::{Hub, HubEntry},
-    tictac::{Cell, TicTacToeGame},
+    auth::{may_play, Claims, Moderator},
+    challenges::BotConfig,
+    hub::{GameConfig, Hub, HubEntry},
 };
-use rocket::{delete, get, post, serde::json::Json, tokio::sync::RwLock, State};
-use rocket_okapi::{openapi, JsonSchema};
-use serde::Deserialize;
-use std::collections::HashMap;
 
-use super::home::{ResetData, TurnData};
+use super::bot::may_take_seat;
+use rocket::{
+    delete, get, post,
+    serde::json::Json,
+    tokio::{sync::RwLock, task::spawn_blocking},
+    State,
+};
+use rocket_okapi::openapi;
+use sea_orm::DatabaseConnection;
+use std::collections::HashMap;
+use tictac::{
+    api::{RegisterData, ResetData, TurnData},
+    notation::{self, GameRecord},
+    Game, GameResult,
+};
 
 #[openapi(tag = "Games")]
 #[get("/games")]
-pub async fn get_games(hub: &State<RwLock<Hub>>) -> Json<HashMap<u64, HubEntry>> {
+pub async fn get_games(
+    hub: &State<RwLock<Hub>>,
+    claims: Option<Claims>,
+) -> Json<HashMap<u64, HubEntry>> {
+    let viewer = claims.map(|c| c.username);
     let mut x = HashMap::new();
     for (k, l) in &hub.read().await.entries {
         let _l = l.read().await;
-        x.insert(*k, _l.clone());
+        x.insert(*k, _l.view_for(viewer.as_deref()));
     }
 
     Json(x)
@@ -26,38 +40,55 @@ pub async fn get_games(hub: &State<RwLock<Hub>>) -> Json<HashMap<u64, HubEntry>>
 #[post("/games", data = "<data>")]
 pub async fn create_game(
     hub: &State<RwLock<Hub>>,
-    _claims: Claims,
+    config: &State<GameConfig>,
+    claims: Claims,
+    moderator: Option<Moderator>,
     data: Json<ResetData>,
 ) -> Json<Option<u64>> {
-    Json(hub.write().await.new_game(data.0.size, data.0.criteria))
+    let mut hub = hub.write().await;
+    if !may_open_game(&hub, config, &claims, &moderator).await {
+        return Json(None);
+    }
+    Json(hub.new_game(data.to_any_game()))
 }
 
 #[openapi(tag = "Games")]
 #[get("/games/<id>")]
-pub async fn get_game_by_id(hub: &State<RwLock<Hub>>, id: u64) -> Option<Json<HubEntry>> {
+pub async fn get_game_by_id(
+    hub: &State<RwLock<Hub>>,
+    id: u64,
+    claims: Option<Claims>,
+) -> Option<Json<HubEntry>> {
+    let viewer = claims.map(|c| c.username);
     Some(Json(
-        hub.read().await.entries.get(&id)?.read().await.clone(),
+        hub.read()
+            .await
+            .entries
+            .get(&id)?
+            .read()
+            .await
+            .view_for(viewer.as_deref()),
     ))
 }
 
-#[derive(Deserialize, JsonSchema)]
-pub struct RegisterData {
-    #[serde(rename = "as")]
-    _as: Cell,
-}
-
 #[openapi(tag = "Games")]
 #[post("/games/<id>/register", data = "<data>")]
 pub async fn game_register(
+    db: &State<DatabaseConnection>,
     hub: &State<RwLock<Hub>>,
+    config: &State<BotConfig>,
     id: u64,
     claims: Claims,
     data: Json<RegisterData>,
 ) -> Option<Json<HubEntry>> {
+    let banned = !may_play(db, &claims.username).await;
     let hub = hub.read().await;
+    let may_sit = !banned && may_take_seat(&hub, config, &claims).await;
     let mut game = hub.entries.get(&id)?.write().await;
-    game.set_player(data.0._as, claims.username);
-    Some(Json(game.clone()))
+    if may_sit {
+        game.set_player(data.side, claims.username.clone(), claims.bot);
+    }
+    Some(Json(game.view_for(Some(&claims.username))))
 }
 
 #[openapi(tag = "Games")]
@@ -70,8 +101,8 @@ pub async fn game_turn(
 ) -> Option<Json<HubEntry>> {
     let hub = hub.read().await;
     let mut game = hub.entries.get(&id)?.write().await;
-    game.turn(data.0.x, data.0.y, claims.username);
-    Some(Json(game.clone()))
+    game.turn(&data.moves(), claims.username.clone());
+    Some(Json(game.view_for(Some(&claims.username))))
 }
 
 #[openapi(tag = "Games")]
@@ -84,10 +115,8 @@ pub async fn game_reset(
 ) -> Option<Json<HubEntry>> {
     let hub = hub.read().await;
     let mut game = hub.entries.get(&id)?.write().await;
-    if game.is_a_player(claims.username) {
-        game.game = TicTacToeGame::new(data.0.size, data.0.criteria);
-    }
-    Some(Json(game.clone()))
+    game.reset(data.to_any_game(), claims.username.clone());
+    Some(Json(game.view_for(Some(&claims.username))))
 }
 
 #[openapi(tag = "Games")]
@@ -96,7 +125,7 @@ pub async fn game_delete(hub: &State<RwLock<Hub>>, id: u64, claims: Claims) -> O
     let mut hub = hub.write().await;
     let allowed = {
         let game = hub.entries.get(&id)?.read().await;
-        game.is_a_player(claims.username) && game.game.draw
+        game.is_a_player(claims.username) && game.game.result() == GameResult::Draw
     };
     if allowed {
         hub.entries.remove(&id);
@@ -105,3 +134,70 @@ pub async fn game_delete(hub: &State<RwLock<Hub>>, id: u64, claims: Claims) -> O
         Some(Json(false))
     }
 }
+
+#[openapi(tag = "Games")]
+#[get("/games/<id>/export?<position>")]
+pub async fn game_export(
+    hub: &State<RwLock<Hub>>,
+    id: u64,
+    position: Option<bool>,
+) -> Option<String> {
+    let hub = hub.read().await;
+    let entry = hub.entries.get(&id)?.read().await.clone();
+    // Writing out a long game takes a while, and the hub is not needed for it
+    drop(hub);
+    if entry.game.hides_information() {
+        return None;
+    }
+    spawn_blocking(move || {
+        if position.unwrap_or(false) {
+            return notation::write_position(&entry.game);
+        }
+        notation::write_game(&GameRecord {
+            game: entry.game,
+            x_player: entry.x_player,
+            o_player: entry.o_player,
+        })
+    })
+    .await
+    .ok()
+}
+
+#[openapi(tag = "Games")]
+#[post("/games/import", data = "<data>")]
+pub async fn game_import(
+    hub: &State<RwLock<Hub>>,
+    config: &State<GameConfig>,
+    claims: Claims,
+    moderator: Option<Moderator>,
+    data: String,
+) -> Json<Option<u64>> {
+    // Parsing replays every move, so it stays off the async workers
+    let game = spawn_blocking(move || {
+        // Game records start with their header, anything else is a single position
+        if data.trim_start().starts_with('[') {
+            notation::parse_game(&data).map(|record| record.game)
+        } else {
+            notation::parse_position(data.trim())
+        }
+    })
+    .await;
+    let mut hub = hub.write().await;
+    if !may_open_game(&hub, config, &claims, &moderator).await {
+        return Json(None);
+    }
+    Json(match game {
+        Ok(Ok(game)) => hub.new_game(game),
+        _ => None,
+    })
+}
+
+/// Whether the caller may open another game, given the limit on unfinished ones
+async fn may_open_game(
+    hub: &Hub,
+    config: &GameConfig,
+    claims: &Claims,
+    moderator: &Option<Moderator>,
+) -> bool {
+    moderator.is_some() || hub.active_games_of(&claims.username).await < config.max_games
+}
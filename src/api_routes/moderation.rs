@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use rocket::{get, post, serde::json::Json, tokio::sync::RwLock, State};
+use rocket_okapi::openapi;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use tictac::{
+    api::{ModerationAction, Report, ReportData, ReportDecision, ReportReason, ReportStatus},
+    notation::{self, GameRecord},
+};
+
+use super::user::account_of;
+use crate::{
+    auth::{Admin, Claims, Moderator},
+    database::{moderation_log, report, user},
+    hub::{Hub, DELETED_PLAYER},
+};
+
+/// Longest comment a report may carry
+const MAX_COMMENT: usize = 1000;
+
+/// A moderation log entry for `action` by `moderator`, for the caller to
+/// fill in and insert along with the action itself
+pub fn audit_entry(moderator: &user::Model, action: &str) -> moderation_log::ActiveModel {
+    moderation_log::ActiveModel {
+        moderator_id: Set(moderator.id),
+        action: Set(action.to_owned()),
+        created_at: Set(Utc::now().timestamp()),
+        ..Default::default()
+    }
+}
+
+/// Usernames by id, with a placeholder for deleted accounts
+async fn names_of(
+    db: &DatabaseConnection,
+    ids: impl IntoIterator<Item = i32>,
+) -> Option<HashMap<i32, String>> {
+    let users = user::Entity::find()
+        .filter(user::Column::Id.is_in(ids))
+        .all(db)
+        .await
+        .ok()?;
+    Some(users.into_iter().map(|u| (u.id, u.username)).collect())
+}
+
+fn name(names: &HashMap<i32, String>, id: i32) -> String {
+    names
+        .get(&id)
+        .cloned()
+        .unwrap_or_else(|| DELETED_PLAYER.to_owned())
+}
+
+/// Reports the caller's opponent in a game to the moderators, with a copy
+/// of the game as it stands. One open report per game and reporter.
+#[openapi(tag = "Games")]
+#[post("/games/<id>/report", data = "<data>")]
+pub async fn report_player(
+    db: &State<DatabaseConnection>,
+    hub: &State<RwLock<Hub>>,
+    id: u64,
+    claims: Claims,
+    data: Json<ReportData>,
+) -> Option<Json<i32>> {
+    let now = Utc::now().timestamp();
+    let reporter = account_of(db, &claims, None).await?;
+    if reporter.is_muted(now) || data.comment.as_ref().map_or(0, String::len) > MAX_COMMENT {
+        return None;
+    }
+    let (opponent, record) = {
+        let hub = hub.read().await;
+        let entry = hub.entries.get(&id)?.read().await;
+        let side = entry.side_of(&claims.username)?;
+        let opponent = entry.get_player(side.flip())?;
+        let record = notation::write_game(&GameRecord {
+            game: entry.game.clone(),
+            x_player: entry.x_player.clone(),
+            o_player: entry.o_player.clone(),
+        });
+        (opponent, record)
+    };
+    let reported = user::Entity::find()
+        .filter(user::Column::Username.eq(opponent))
+        .one(&**db)
+        .await
+        .ok()??;
+    if reported.id == reporter.id {
+        return None;
+    }
+    let open = report::Entity::find()
+        .filter(report::Column::ReporterId.eq(reporter.id))
+        .filter(report::Column::GameId.eq(id as i64))
+        .filter(report::Column::Status.eq(ReportStatus::Open.as_str()))
+        .one(&**db)
+        .await
+        .ok()?;
+    if open.is_some() {
+        return None;
+    }
+    let row = report::ActiveModel {
+        reporter_id: Set(reporter.id),
+        reported_id: Set(reported.id),
+        game_id: Set(id as i64),
+        reason: Set(data.reason.as_str().to_owned()),
+        comment: Set(data.comment.clone()),
+        game_record: Set(Some(record)),
+        status: Set(ReportStatus::Open.as_str().to_owned()),
+        created_at: Set(now),
+        ..Default::default()
+    };
+    Some(Json(row.insert(&**db).await.ok()?.id))
+}
+
+/// The moderation queue: reports with `status` (`open` by default), oldest
+/// first, `limit` (at most 100) at a time
+#[openapi(tag = "Admin")]
+#[get("/moderation/reports?<status>&<offset>&<limit>")]
+pub async fn list_reports(
+    db: &State<DatabaseConnection>,
+    _moderator: Moderator,
+    status: Option<String>,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Option<Json<Vec<Report>>> {
+    let status = match status {
+        Some(status) => ReportStatus::parse(&status)?,
+        None => ReportStatus::Open,
+    };
+    let reports = report::Entity::find()
+        .filter(report::Column::Status.eq(status.as_str()))
+        .order_by_asc(report::Column::Id)
+        .offset(offset.unwrap_or(0))
+        .limit(limit.unwrap_or(50).min(100))
+        .all(&**db)
+        .await
+        .ok()?;
+    let ids = reports
+        .iter()
+        .flat_map(|r| [Some(r.reporter_id), Some(r.reported_id), r.handled_by])
+        .flatten();
+    let names = names_of(db, ids.collect::<Vec<_>>()).await?;
+    let reports = reports.into_iter().map(|r| Report {
+        id: r.id,
+        reporter: name(&names, r.reporter_id),
+        reported: name(&names, r.reported_id),
+        game_id: r.game_id as u64,
+        reason: ReportReason::parse(&r.reason).unwrap_or(ReportReason::Abuse),
+        comment: r.comment,
+        game_record: r.game_record,
+        status: ReportStatus::parse(&r.status).unwrap_or(ReportStatus::Open),
+        created_at: r.created_at,
+        handled_by: r.handled_by.map(|id| name(&names, id)),
+    });
+    Some(Json(reports.collect()))
+}
+
+/// Closes an open report as resolved or dismissed. Sanctions are handed out
+/// separately, through the ban and mute routes.
+#[openapi(tag = "Admin")]
+#[post("/moderation/reports/<id>", data = "<data>")]
+pub async fn decide_report(
+    db: &State<DatabaseConnection>,
+    moderator: Moderator,
+    id: i32,
+    data: Json<ReportDecision>,
+) -> Option<Json<bool>> {
+    let action = match data.status {
+        ReportStatus::Open => return None,
+        ReportStatus::Resolved => "resolve_report",
+        ReportStatus::Dismissed => "dismiss_report",
+    };
+    let now = Utc::now().timestamp();
+    let txn = db.begin().await.ok()?;
+    // Two moderators closing the same report: only the first one counts
+    let closed = report::Entity::update_many()
+        .col_expr(report::Column::Status, Expr::value(data.status.as_str()))
+        .col_expr(report::Column::HandledBy, Expr::value(moderator.user.id))
+        .col_expr(report::Column::HandledAt, Expr::value(now))
+        .filter(report::Column::Id.eq(id))
+        .filter(report::Column::Status.eq(ReportStatus::Open.as_str()))
+        .exec(&txn)
+        .await
+        .ok()?;
+    if closed.rows_affected != 1 {
+        return Some(Json(false));
+    }
+    let mut entry = audit_entry(&moderator.user, action);
+    entry.report_id = Set(Some(id));
+    entry.detail = Set(data.note.clone());
+    entry.insert(&txn).await.ok()?;
+    txn.commit().await.ok()?;
+    Some(Json(true))
+}
+
+/// The moderation log, newest first, `limit` (at most 100) at a time
+#[openapi(tag = "Admin")]
+#[get("/admin/log?<offset>&<limit>")]
+pub async fn get_moderation_log(
+    db: &State<DatabaseConnection>,
+    _admin: Admin,
+    offset: Option<u64>,
+    limit: Option<u64>,
+) -> Option<Json<Vec<ModerationAction>>> {
+    let entries = moderation_log::Entity::find()
+        .order_by_desc(moderation_log::Column::Id)
+        .offset(offset.unwrap_or(0))
+        .limit(limit.unwrap_or(50).min(100))
+        .all(&**db)
+        .await
+        .ok()?;
+    let ids = entries
+        .iter()
+        .flat_map(|e| [Some(e.moderator_id), e.target_id])
+        .flatten();
+    let names = names_of(db, ids.collect::<Vec<_>>()).await?;
+    let entries = entries.into_iter().map(|e| ModerationAction {
+        id: e.id,
+        moderator: name(&names, e.moderator_id),
+        action: e.action,
+        target: e.target_id.map(|id| name(&names, id)),
+        game_id: e.game_id.map(|id| id as u64),
+        report_id: e.report_id,
+        detail: e.detail,
+        created_at: e.created_at,
+    });
+    Some(Json(entries.collect()))
+}
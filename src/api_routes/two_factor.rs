@@ -0,0 +1,246 @@
+use chrono::Utc;
+use rocket::{post, serde::json::Json, State};
+use rocket_okapi::openapi;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+use tictac::api::{SecondFactorData, TokenPair, TotpCode, TotpEnrollment};
+
+use crate::{
+    auth::{hash_token, Claims, SessionConfig, UserAgent},
+    database::{recovery_code, user},
+    keys::Keys,
+    throttle::{self, ClientIp, LoginThrottle, TooManyRequests},
+    totp::{self, SecretBox, TotpConfig},
+};
+
+use super::user::{account_of, start_session};
+
+/// What the password step of a login hands out to accounts with 2FA. It is
+/// signed like access tokens, but doesn't pass for one.
+#[derive(Serialize, Deserialize)]
+struct SecondFactorTicket {
+    /// Id of the user
+    second_factor: i32,
+    exp: i64,
+}
+
+/// How long the second step may take
+const TICKET_SECONDS: i64 = 5 * 60;
+const RECOVERY_CODES: usize = 10;
+
+pub fn ticket_for(keys: &Keys, user_id: i32) -> Option<String> {
+    keys.encode(&SecondFactorTicket {
+        second_factor: user_id,
+        exp: Utc::now().timestamp() + TICKET_SECONDS,
+    })
+}
+
+fn normalize(code: &str) -> String {
+    code.trim().to_lowercase()
+}
+
+/// Replaces the recovery codes of the user with new ones
+async fn new_recovery_codes(db: &DatabaseConnection, user_id: i32) -> Option<Vec<String>> {
+    recovery_code::Entity::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .ok()?;
+    let mut codes = Vec::new();
+    for _ in 0..RECOVERY_CODES {
+        let (a, b): (u16, u16) = rand::random();
+        let code = format!("{a:04x}-{b:04x}");
+        let row = recovery_code::ActiveModel {
+            user_id: Set(user_id),
+            code_hash: Set(hash_token(&code)),
+            ..Default::default()
+        };
+        row.insert(db).await.ok()?;
+        codes.push(code);
+    }
+    Some(codes)
+}
+
+/// Whether `code` is a current TOTP code of `user` that wasn't used before.
+/// Its step is stored, so the same code can't be replayed.
+async fn totp_accepted(
+    db: &DatabaseConnection,
+    secrets: &SecretBox,
+    user: &user::Model,
+    code: &str,
+) -> bool {
+    let secret = match user.totp_secret.as_deref().and_then(|s| secrets.open(s)) {
+        Some(secret) => secret,
+        None => return false,
+    };
+    let step = match totp::verify(&secret, code, Utc::now().timestamp(), user.totp_last_step) {
+        Some(step) => step,
+        None => return false,
+    };
+    // Two requests with the same code: only the first one counts
+    let stored = user::Entity::update_many()
+        .col_expr(user::Column::TotpLastStep, Expr::value(step))
+        .filter(user::Column::Id.eq(user.id))
+        .filter(
+            Condition::any()
+                .add(user::Column::TotpLastStep.is_null())
+                .add(user::Column::TotpLastStep.lt(step)),
+        )
+        .exec(db)
+        .await;
+    matches!(stored, Ok(result) if result.rows_affected == 1)
+}
+
+/// Whether `code` is a TOTP code of `user`, or one of their recovery codes,
+/// which is then used up
+async fn check_code(
+    db: &DatabaseConnection,
+    secrets: &SecretBox,
+    user: &user::Model,
+    code: &str,
+) -> bool {
+    if !user.totp_enabled {
+        return false;
+    }
+    if totp_accepted(db, secrets, user, code).await {
+        return true;
+    }
+    let used = recovery_code::Entity::delete_many()
+        .filter(recovery_code::Column::UserId.eq(user.id))
+        .filter(recovery_code::Column::CodeHash.eq(hash_token(&normalize(code))))
+        .exec(db)
+        .await;
+    matches!(used, Ok(result) if result.rows_affected == 1)
+}
+
+/// Second step of a login with 2FA: the ticket from `/user/login` and a code
+#[openapi(tag = "User control")]
+#[post("/user/login/second-factor", data = "<data>")]
+pub async fn login_second_factor(
+    db: &State<DatabaseConnection>,
+    keys: &State<Keys>,
+    secrets: &State<SecretBox>,
+    config: &State<SessionConfig>,
+    throttle: &State<LoginThrottle>,
+    ip: ClientIp,
+    user_agent: UserAgent,
+    data: Json<SecondFactorData>,
+) -> Result<Json<Option<TokenPair>>, TooManyRequests> {
+    let ticket = match keys.decode::<SecondFactorTicket>(&data.ticket) {
+        Some(ticket) => ticket,
+        None => return Ok(Json(None)),
+    };
+    let user = match user::Entity::find_by_id(ticket.second_factor)
+        .one(&**db)
+        .await
+    {
+        Ok(Some(user)) => user,
+        _ => return Ok(Json(None)),
+    };
+    let now = Utc::now().timestamp();
+    if let Some(wait) = throttle.check(ip.0, &user.username, now).await {
+        throttle::audit(db, &user.username, ip.0, throttle::THROTTLED).await;
+        return Err(TooManyRequests(wait));
+    }
+    Ok(Json(if check_code(db, secrets, &user, &data.code).await {
+        throttle.succeeded(&user.username).await;
+        start_session(db, keys, config, user, user_agent.0).await
+    } else {
+        throttle.failed(ip.0, &user.username, now).await;
+        throttle::audit(db, &user.username, ip.0, throttle::SECOND_FACTOR).await;
+        None
+    }))
+}
+
+/// Starts 2FA setup with a new secret. It takes effect once a code from it
+/// is sent to `/user/2fa/enable`.
+#[openapi(tag = "User control")]
+#[post("/user/2fa/enroll")]
+pub async fn enroll_totp(
+    db: &State<DatabaseConnection>,
+    secrets: &State<SecretBox>,
+    config: &State<TotpConfig>,
+    claims: Claims,
+) -> Option<Json<TotpEnrollment>> {
+    let user = account_of(db, &claims, None).await?;
+    if user.totp_enabled {
+        return None;
+    }
+    let secret = totp::new_secret();
+    let uri = totp::otpauth_uri(&config.issuer, &user.username, &secret);
+    let mut account: user::ActiveModel = user.into();
+    account.totp_secret = Set(Some(secrets.seal(&secret)));
+    account.totp_last_step = Set(None);
+    account.update(&**db).await.ok()?;
+    Some(Json(TotpEnrollment {
+        secret: totp::base32(&secret),
+        qr_svg: totp::qr_svg(&uri)?,
+        uri,
+    }))
+}
+
+/// Turns 2FA on with a code from the enrolled secret. Answers with the
+/// recovery codes, which are only ever shown here.
+#[openapi(tag = "User control")]
+#[post("/user/2fa/enable", data = "<data>")]
+pub async fn enable_totp(
+    db: &State<DatabaseConnection>,
+    secrets: &State<SecretBox>,
+    claims: Claims,
+    data: Json<TotpCode>,
+) -> Option<Json<Vec<String>>> {
+    let user = account_of(db, &claims, None).await?;
+    if user.totp_enabled || !totp_accepted(db, secrets, &user, &data.code).await {
+        return None;
+    }
+    let id = user.id;
+    let mut account: user::ActiveModel = user.into();
+    account.totp_enabled = Set(true);
+    account.update(&**db).await.ok()?;
+    Some(Json(new_recovery_codes(db, id).await?))
+}
+
+/// Turns 2FA off, with a TOTP or recovery code
+#[openapi(tag = "User control")]
+#[post("/user/2fa/disable", data = "<data>")]
+pub async fn disable_totp(
+    db: &State<DatabaseConnection>,
+    secrets: &State<SecretBox>,
+    claims: Claims,
+    data: Json<TotpCode>,
+) -> Option<Json<bool>> {
+    let user = account_of(db, &claims, None).await?;
+    if !check_code(db, secrets, &user, &data.code).await {
+        return None;
+    }
+    let id = user.id;
+    let mut account: user::ActiveModel = user.into();
+    account.totp_enabled = Set(false);
+    account.totp_secret = Set(None);
+    account.update(&**db).await.ok()?;
+    recovery_code::Entity::delete_many()
+        .filter(recovery_code::Column::UserId.eq(id))
+        .exec(&**db)
+        .await
+        .ok()?;
+    Some(Json(true))
+}
+
+/// New recovery codes in place of the old ones, with a TOTP code
+#[openapi(tag = "User control")]
+#[post("/user/2fa/recovery-codes", data = "<data>")]
+pub async fn new_totp_recovery_codes(
+    db: &State<DatabaseConnection>,
+    secrets: &State<SecretBox>,
+    claims: Claims,
+    data: Json<TotpCode>,
+) -> Option<Json<Vec<String>>> {
+    let user = account_of(db, &claims, None).await?;
+    if !user.totp_enabled || !totp_accepted(db, secrets, &user, &data.code).await {
+        return None;
+    }
+    Some(Json(new_recovery_codes(db, user.id).await?))
+}
@@ -1,23 +1,112 @@
-use chrono::{Days, Utc};
-use rocket::{post, serde::json::Json, State};
-use rocket_okapi::{okapi::schemars::JsonSchema, openapi};
+use chrono::Utc;
+use rocket::{delete, get, post, serde::json::Json, tokio::sync::RwLock, State};
+use rocket_okapi::openapi;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use tictac::api::{
+    DeleteAccountData, JwkSet, LoginResult, PasswordChange, RefreshData, RegistrationResult,
+    RenameData, SessionInfo, TokenPair, UserData,
 };
-use serde::{Deserialize, Serialize};
 
-use crate::auth::{encode_token, Claims, Secret};
+use super::two_factor::ticket_for;
+use crate::{
+    auth::{hash_token, new_refresh_token, Claims, SessionConfig, UserAgent},
+    challenges::Challenges,
+    database::{api_key, recovery_code, session, user, user_token},
+    hub::{Hub, DELETED_PLAYER},
+    keys::Keys,
+    throttle::{self, ClientIp, LoginThrottle, TooManyRequests},
+};
 
-#[derive(Deserialize, JsonSchema)]
-pub struct UserData {
+fn token_pair(
+    keys: &Keys,
+    config: &SessionConfig,
     username: String,
-    password: String,
+    sid: i32,
+    refresh_token: String,
+) -> Option<TokenPair> {
+    let expires_in = config.access_minutes * 60;
+    let claims = Claims {
+        username,
+        exp: Utc::now().timestamp() + expires_in,
+        bot: false,
+        sid: Some(sid),
+    };
+    Some(TokenPair {
+        access_token: keys.encode(&claims)?,
+        refresh_token,
+        expires_in,
+    })
 }
 
-#[derive(Serialize, JsonSchema)]
-pub struct RegistrationResult {
-    id: i32,
-    username: String,
+/// Opens a session for `user` and hands out its first tokens
+pub async fn start_session(
+    db: &DatabaseConnection,
+    keys: &Keys,
+    config: &SessionConfig,
+    user: user::Model,
+    user_agent: Option<String>,
+) -> Option<TokenPair> {
+    let now = Utc::now().timestamp();
+    if user.is_banned(now) {
+        return None;
+    }
+    // Expired sessions of the user go away on the next login
+    session::Entity::delete_many()
+        .filter(session::Column::UserId.eq(user.id))
+        .filter(session::Column::ExpiresAt.lte(now))
+        .exec(db)
+        .await
+        .ok()?;
+    let refresh_token = new_refresh_token();
+    let session = session::ActiveModel {
+        user_id: Set(user.id),
+        token_hash: Set(hash_token(&refresh_token)),
+        user_agent: Set(user_agent),
+        created_at: Set(now),
+        last_used_at: Set(now),
+        expires_at: Set(now + config.refresh_days * 24 * 60 * 60),
+        ..Default::default()
+    };
+    let session = session.insert(db).await.ok()?;
+    token_pair(keys, config, user.username, session.id, refresh_token)
+}
+
+/// The session the caller's access token belongs to
+async fn current_session(db: &DatabaseConnection, claims: &Claims) -> Option<session::Model> {
+    session::Entity::find_by_id(claims.sid?)
+        .one(db)
+        .await
+        .ok()?
+}
+
+/// The caller's own account, checking `password` when given. Bots have
+/// none to manage.
+pub async fn account_of(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    password: Option<&str>,
+) -> Option<user::Model> {
+    if claims.bot {
+        return None;
+    }
+    let mut condition = Condition::all().add(user::Column::Username.eq(claims.username.clone()));
+    if let Some(password) = password {
+        condition = condition.add(user::Column::Password.eq(password));
+    }
+    user::Entity::find().filter(condition).one(db).await.ok()?
+}
+
+/// Revokes every token of the user
+pub async fn end_all_sessions(db: &DatabaseConnection, user_id: i32) -> Option<()> {
+    session::Entity::delete_many()
+        .filter(session::Column::UserId.eq(user_id))
+        .exec(db)
+        .await
+        .ok()?;
+    Some(())
 }
 
 #[openapi(tag = "User control")]
@@ -26,10 +115,13 @@ pub async fn register(
     db: &State<DatabaseConnection>,
     data: Json<UserData>,
 ) -> Option<Json<RegistrationResult>> {
-    use crate::database::user;
+    if data.username.is_empty() || data.username == DELETED_PLAYER {
+        return None;
+    }
     let new_user = user::ActiveModel {
         username: Set(data.username.clone()),
         password: Set(data.password.clone()),
+        is_bot: Set(false),
         ..Default::default()
     };
     let user = new_user.insert(&**db).await.ok()?;
@@ -40,40 +132,302 @@ pub async fn register(
     Some(Json(returned))
 }
 
+/// Starts a session: a short-lived access token and a refresh token. With
+/// 2FA, a ticket for the second step instead. Repeated failures make the
+/// next attempt wait, see `Retry-After`.
 #[openapi(tag = "User control")]
 #[post("/user/login", data = "<data>")]
 pub async fn login(
     db: &State<DatabaseConnection>,
     data: Json<UserData>,
-    secret: &State<Secret>,
-) -> Json<Option<String>> {
-    use crate::database::user;
+    keys: &State<Keys>,
+    config: &State<SessionConfig>,
+    throttle: &State<LoginThrottle>,
+    ip: ClientIp,
+    user_agent: UserAgent,
+) -> Result<Json<Option<LoginResult>>, TooManyRequests> {
+    let now = Utc::now().timestamp();
+    if let Some(wait) = throttle.check(ip.0, &data.username, now).await {
+        throttle::audit(db, &data.username, ip.0, throttle::THROTTLED).await;
+        return Err(TooManyRequests(wait));
+    }
     let x = user::Entity::find()
         .filter(
             Condition::all()
                 .add(user::Column::Username.eq(data.username.clone()))
-                .add(user::Column::Password.eq(data.password.clone())),
+                .add(user::Column::Password.eq(data.password.clone()))
+                // Bots log in with API keys only
+                .add(user::Column::IsBot.eq(false)),
         )
         .one(&**db)
         .await;
 
-    let timestamp = Utc::now()
-        .checked_add_days(Days::new(1))
-        .unwrap()
-        .timestamp();
-    Json(if let Ok(Some(_user)) = x {
-        let claims = Claims {
-            username: _user.username,
-            exp: timestamp,
-        };
-        encode_token(&claims, &secret.0)
-    } else {
-        None
+    Ok(Json(match x {
+        // The failures are only forgotten once the second step succeeds
+        Ok(Some(user)) if user.totp_enabled => {
+            ticket_for(keys, user.id).map(|ticket| LoginResult::SecondFactor { ticket })
+        }
+        Ok(Some(user)) => {
+            throttle.succeeded(&user.username).await;
+            start_session(db, keys, config, user, user_agent.0)
+                .await
+                .map(LoginResult::LoggedIn)
+        }
+        Ok(None) => {
+            throttle.failed(ip.0, &data.username, now).await;
+            throttle::audit(db, &data.username, ip.0, throttle::PASSWORD).await;
+            None
+        }
+        Err(_) => None,
+    }))
+}
+
+/// Trades a refresh token for a new pair. The old refresh token stops
+/// working, so each one can only be used once.
+#[openapi(tag = "User control")]
+#[post("/user/refresh", data = "<data>")]
+pub async fn refresh(
+    db: &State<DatabaseConnection>,
+    data: Json<RefreshData>,
+    keys: &State<Keys>,
+    config: &State<SessionConfig>,
+) -> Json<Option<TokenPair>> {
+    let now = Utc::now().timestamp();
+    let old_hash = hash_token(&data.refresh_token);
+    let session = match session::Entity::find()
+        .filter(session::Column::TokenHash.eq(old_hash.clone()))
+        .filter(session::Column::ExpiresAt.gt(now))
+        .one(&**db)
+        .await
+    {
+        Ok(Some(session)) => session,
+        _ => return Json(None),
+    };
+    let user = match user::Entity::find_by_id(session.user_id).one(&**db).await {
+        Ok(Some(user)) => user,
+        _ => return Json(None),
+    };
+    let refresh_token = new_refresh_token();
+    // Only one of two concurrent refreshes with the same token gets through
+    let rotated = session::Entity::update_many()
+        .col_expr(
+            session::Column::TokenHash,
+            Expr::value(hash_token(&refresh_token)),
+        )
+        .col_expr(session::Column::LastUsedAt, Expr::value(now))
+        .col_expr(
+            session::Column::ExpiresAt,
+            Expr::value(now + config.refresh_days * 24 * 60 * 60),
+        )
+        .filter(session::Column::Id.eq(session.id))
+        .filter(session::Column::TokenHash.eq(old_hash))
+        .exec(&**db)
+        .await;
+    Json(match rotated {
+        Ok(result) if result.rows_affected == 1 => {
+            token_pair(keys, config, user.username, session.id, refresh_token)
+        }
+        _ => None,
     })
 }
 
+/// Ends the caller's session, or with `all` every session of theirs. Their
+/// access tokens stop working right away.
+#[openapi(tag = "User control")]
+#[post("/user/logout?<all>")]
+pub async fn logout(
+    db: &State<DatabaseConnection>,
+    claims: Claims,
+    all: Option<bool>,
+) -> Option<Json<bool>> {
+    let current = current_session(db, &claims).await?;
+    let condition = if all.unwrap_or(false) {
+        session::Column::UserId.eq(current.user_id)
+    } else {
+        session::Column::Id.eq(current.id)
+    };
+    let result = session::Entity::delete_many()
+        .filter(condition)
+        .exec(&**db)
+        .await
+        .ok()?;
+    Some(Json(result.rows_affected > 0))
+}
+
+/// The caller's sessions, oldest first
+#[openapi(tag = "User control")]
+#[get("/user/sessions")]
+pub async fn get_sessions(
+    db: &State<DatabaseConnection>,
+    claims: Claims,
+) -> Option<Json<Vec<SessionInfo>>> {
+    let current = current_session(db, &claims).await?;
+    let sessions = session::Entity::find()
+        .filter(session::Column::UserId.eq(current.user_id))
+        .filter(session::Column::ExpiresAt.gt(Utc::now().timestamp()))
+        .order_by_asc(session::Column::CreatedAt)
+        .all(&**db)
+        .await
+        .ok()?;
+    Some(Json(
+        sessions
+            .into_iter()
+            .map(|s| SessionInfo {
+                current: s.id == current.id,
+                id: s.id,
+                user_agent: s.user_agent,
+                created_at: s.created_at,
+                last_used_at: s.last_used_at,
+                expires_at: s.expires_at,
+            })
+            .collect(),
+    ))
+}
+
+/// Ends one of the caller's sessions, e.g. on a lost device
+#[openapi(tag = "User control")]
+#[delete("/user/sessions/<id>")]
+pub async fn end_session(
+    db: &State<DatabaseConnection>,
+    claims: Claims,
+    id: i32,
+) -> Option<Json<bool>> {
+    let current = current_session(db, &claims).await?;
+    let result = session::Entity::delete_many()
+        .filter(session::Column::Id.eq(id))
+        .filter(session::Column::UserId.eq(current.user_id))
+        .exec(&**db)
+        .await
+        .ok()?;
+    Some(Json(result.rows_affected > 0))
+}
+
 #[openapi(tag = "User control")]
 #[post("/user/check")]
 pub async fn check_logged_in(claims: Option<Claims>) -> Json<Option<String>> {
     Json(claims.map(|x| x.username))
 }
+
+/// Public keys that verify access tokens, for other services
+#[openapi(tag = "User control")]
+#[get("/.well-known/jwks.json")]
+pub async fn jwks(keys: &State<Keys>) -> Json<JwkSet> {
+    Json(keys.jwks().clone())
+}
+
+/// Changes the caller's password. Every session ends, and the caller gets a
+/// new one.
+#[openapi(tag = "User control")]
+#[post("/user/password", data = "<data>")]
+pub async fn change_password(
+    db: &State<DatabaseConnection>,
+    keys: &State<Keys>,
+    config: &State<SessionConfig>,
+    user_agent: UserAgent,
+    claims: Claims,
+    data: Json<PasswordChange>,
+) -> Option<Json<TokenPair>> {
+    let user = account_of(db, &claims, Some(&data.old_password)).await?;
+    let mut account: user::ActiveModel = user.into();
+    account.password = Set(data.new_password.clone());
+    let user = account.update(&**db).await.ok()?;
+    end_all_sessions(db, user.id).await?;
+    Some(Json(
+        start_session(db, keys, config, user, user_agent.0).await?,
+    ))
+}
+
+/// Changes the caller's username, in their games and challenges too. Every
+/// session ends, and the caller gets a new one.
+#[openapi(tag = "User control")]
+#[post("/user/rename", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn rename(
+    db: &State<DatabaseConnection>,
+    hub: &State<RwLock<Hub>>,
+    challenges: &State<Challenges>,
+    keys: &State<Keys>,
+    config: &State<SessionConfig>,
+    user_agent: UserAgent,
+    claims: Claims,
+    data: Json<RenameData>,
+) -> Option<Json<TokenPair>> {
+    if data.username.is_empty() || data.username == DELETED_PLAYER {
+        return None;
+    }
+    let user = account_of(db, &claims, None).await?;
+    let old = user.username.clone();
+    let mut account: user::ActiveModel = user.into();
+    account.username = Set(data.username.clone());
+    // Fails when the name is taken
+    let user = account.update(&**db).await.ok()?;
+    hub.read()
+        .await
+        .rename_player(&old, Some(&user.username))
+        .await;
+    challenges.rename(&old, Some(&user.username)).await;
+    end_all_sessions(db, user.id).await?;
+    Some(Json(
+        start_session(db, keys, config, user, user_agent.0).await?,
+    ))
+}
+
+/// Deletes the caller's account along with their bots. Finished games keep
+/// a placeholder instead of the name; seats in unfinished ones are freed.
+/// Reports by or against the account stay for the moderators.
+#[openapi(tag = "User control")]
+#[delete("/user", data = "<data>")]
+pub async fn delete_account(
+    db: &State<DatabaseConnection>,
+    hub: &State<RwLock<Hub>>,
+    challenges: &State<Challenges>,
+    claims: Claims,
+    data: Json<DeleteAccountData>,
+) -> Option<Json<bool>> {
+    let user = account_of(db, &claims, Some(&data.password)).await?;
+    let mut accounts = user::Entity::find()
+        .filter(user::Column::OwnerId.eq(user.id))
+        .all(&**db)
+        .await
+        .ok()?;
+    accounts.push(user);
+    // All or nothing, so a failure can't leave an account that still logs in
+    let txn = db.begin().await.ok()?;
+    for account in &accounts {
+        // SQLite only cascades with foreign keys turned on, so do it by hand
+        session::Entity::delete_many()
+            .filter(session::Column::UserId.eq(account.id))
+            .exec(&txn)
+            .await
+            .ok()?;
+        api_key::Entity::delete_many()
+            .filter(api_key::Column::UserId.eq(account.id))
+            .exec(&txn)
+            .await
+            .ok()?;
+        user_token::Entity::delete_many()
+            .filter(user_token::Column::UserId.eq(account.id))
+            .exec(&txn)
+            .await
+            .ok()?;
+        recovery_code::Entity::delete_many()
+            .filter(recovery_code::Column::UserId.eq(account.id))
+            .exec(&txn)
+            .await
+            .ok()?;
+        user::Entity::delete_many()
+            .filter(user::Column::Id.eq(account.id))
+            .exec(&txn)
+            .await
+            .ok()?;
+    }
+    txn.commit().await.ok()?;
+    for account in accounts {
+        hub.read()
+            .await
+            .rename_player(&account.username, None)
+            .await;
+        challenges.rename(&account.username, None).await;
+    }
+    Some(Json(true))
+}
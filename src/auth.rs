@@ -1,4 +1,4 @@
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use chrono::Utc;
 use rocket::{
     async_trait,
     request::{self, FromRequest},
@@ -8,25 +8,133 @@ use rocket_okapi::{
     okapi::openapi3::{Object, SecurityRequirement, SecurityScheme, SecuritySchemeData},
     request::{OpenApiFromRequest, RequestHeaderInput},
 };
+use sea_orm::{sea_query::Expr, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tictac::api::Role;
 
-pub struct Secret(pub String);
+use crate::{database::user, keys::Keys};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     // exp: usize,
     pub username: String,
     pub exp: i64,
+    /// Set when the request came with a bot's API key
+    #[serde(default)]
+    pub bot: bool,
+    /// Session the access token belongs to. Ending the session revokes it.
+    #[serde(default)]
+    pub sid: Option<i32>,
 }
 
-#[allow(dead_code)]
-pub fn encode_token(claims: &Claims, secret: &str) -> Option<String> {
-    encode(
-        &Header::default(),
-        claims,
-        &EncodingKey::from_secret(secret.as_ref()),
+/// Token lifetimes, from the `sessions` table in `Rocket.toml`
+#[derive(Deserialize)]
+pub struct SessionConfig {
+    pub access_minutes: i64,
+    pub refresh_days: i64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            access_minutes: 15,
+            refresh_days: 30,
+        }
+    }
+}
+
+/// Prefix that tells API keys apart from JWTs in the `Authorization` header
+pub const API_KEY_PREFIX: &str = "ttb_";
+
+fn random_token(prefix: &str) -> String {
+    let bytes: [u8; 24] = rand::random();
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!("{prefix}{hex}")
+}
+
+pub fn new_api_key() -> String {
+    random_token(API_KEY_PREFIX)
+}
+
+pub fn new_refresh_token() -> String {
+    random_token("ttr_")
+}
+
+/// Verification and password reset tokens sent by mail
+pub fn new_mail_token() -> String {
+    random_token("ttm_")
+}
+
+/// What gets stored of API keys and refresh tokens
+pub fn hash_token(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// The bot an API key belongs to
+pub async fn bot_claims(db: &DatabaseConnection, key: &str) -> Option<Claims> {
+    use crate::database::api_key;
+    let key = api_key::Entity::find()
+        .filter(api_key::Column::KeyHash.eq(hash_token(key)))
+        .one(db)
+        .await
+        .ok()??;
+    let bot = user::Entity::find_by_id(key.user_id).one(db).await.ok()??;
+    if is_banned(db, &bot, Utc::now().timestamp()).await {
+        return None;
+    }
+    Some(Claims {
+        username: bot.username,
+        // API keys don't expire, they are revoked
+        exp: i64::MAX,
+        bot: bot.is_bot,
+        sid: None,
+    })
+}
+
+/// Whether `account` is banned, or owned by a banned account
+async fn is_banned(db: &DatabaseConnection, account: &user::Model, now: i64) -> bool {
+    if account.is_banned(now) {
+        return true;
+    }
+    match account.owner_id {
+        Some(id) => matches!(
+            user::Entity::find_by_id(id).one(db).await,
+            Ok(Some(owner)) if owner.is_banned(now)
+        ),
+        None => false,
+    }
+}
+
+/// Whether `username` may sit down in games. Checked when seats are taken,
+/// as bans may be newer than the caller's token.
+pub async fn may_play(db: &DatabaseConnection, username: &str) -> bool {
+    match user::Entity::find()
+        .filter(user::Column::Username.eq(username))
+        .one(db)
+        .await
+    {
+        Ok(Some(account)) => !is_banned(db, &account, Utc::now().timestamp()).await,
+        _ => false,
+    }
+}
+
+/// Whether the session an access token was issued for is still there, with
+/// an account that isn't banned
+async fn session_alive(db: &DatabaseConnection, sid: i32) -> bool {
+    use crate::database::session;
+    let now = Utc::now().timestamp();
+    let session = match session::Entity::find_by_id(sid).one(db).await {
+        Ok(Some(session)) if session.expires_at > now => session,
+        _ => return false,
+    };
+    matches!(
+        user::Entity::find_by_id(session.user_id).one(db).await,
+        Ok(Some(user)) if !user.is_banned(now)
     )
-    .ok()
 }
 
 #[async_trait]
@@ -34,20 +142,31 @@ impl<'r> FromRequest<'r> for Claims {
     type Error = ();
 
     async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
-        let secret_state: &State<Secret> =
-            FromRequest::from_request(req).await.succeeded().unwrap();
-        let validation = Validation::default();
+        let keys: &State<Keys> = FromRequest::from_request(req).await.succeeded().unwrap();
         if let Some(auth_header) = req.headers().get_one("Authorization") {
-            if let Some(token) = auth_header.strip_prefix("Bearer ") {
-                let claims = decode::<Claims>(
-                    token,
-                    &DecodingKey::from_secret(secret_state.0.as_ref()),
-                    &validation,
-                );
-                if let Ok(data) = claims {
-                    request::Outcome::Success(data.claims)
-                } else {
-                    request::Outcome::Forward(())
+            if let Some(key) = auth_header
+                .strip_prefix("Bearer ")
+                .filter(|token| token.starts_with(API_KEY_PREFIX))
+            {
+                let db: &State<DatabaseConnection> =
+                    FromRequest::from_request(req).await.succeeded().unwrap();
+                match bot_claims(db, key).await {
+                    Some(claims) => request::Outcome::Success(claims),
+                    None => request::Outcome::Forward(()),
+                }
+            } else if let Some(token) = auth_header.strip_prefix("Bearer ") {
+                let claims = keys.decode::<Claims>(token);
+                let db: &State<DatabaseConnection> =
+                    FromRequest::from_request(req).await.succeeded().unwrap();
+                // Tokens without a session predate revocation and are refused
+                match claims {
+                    Some(claims) => match claims.sid {
+                        Some(sid) if session_alive(db, sid).await => {
+                            request::Outcome::Success(claims)
+                        }
+                        _ => request::Outcome::Forward(()),
+                    },
+                    None => request::Outcome::Forward(()),
                 }
             } else {
                 request::Outcome::Forward(())
@@ -66,7 +185,8 @@ impl<'a> OpenApiFromRequest<'a> for Claims {
     ) -> rocket_okapi::Result<rocket_okapi::request::RequestHeaderInput> {
         let security_scheme = SecurityScheme {
             description: Some(
-                "Requres a Bearer JWT token in Authorization header to accept".to_owned(),
+                "Requres a Bearer JWT token (or a bot's API key) in Authorization header to accept"
+                    .to_owned(),
             ),
             data: SecuritySchemeData::Http {
                 scheme: "bearer".to_owned(),
@@ -83,3 +203,122 @@ impl<'a> OpenApiFromRequest<'a> for Claims {
         ))
     }
 }
+
+/// From the `admin` table in `Rocket.toml`
+#[derive(Deserialize, Default)]
+pub struct AdminConfig {
+    /// Accounts made admins at startup, to get the first admin in
+    #[serde(default)]
+    pub usernames: Vec<String>,
+}
+
+pub async fn promote_admins(db: &DatabaseConnection, config: &AdminConfig) {
+    if config.usernames.is_empty() {
+        return;
+    }
+    user::Entity::update_many()
+        .col_expr(user::Column::Role, Expr::value(Role::Admin.as_str()))
+        .filter(user::Column::Username.is_in(config.usernames.clone()))
+        .filter(user::Column::IsBot.eq(false))
+        .exec(db)
+        .await
+        .expect("could not promote the configured admins");
+}
+
+/// [`Claims`] of a person with at least the role `min`. The role is looked up
+/// rather than carried in the token, so taking it away works at once.
+async fn claims_with_role(
+    req: &Request<'_>,
+    min: Role,
+) -> request::Outcome<(Claims, user::Model), ()> {
+    let claims = match Claims::from_request(req).await {
+        request::Outcome::Success(claims) if !claims.bot => claims,
+        _ => return request::Outcome::Forward(()),
+    };
+    let db: &State<DatabaseConnection> = FromRequest::from_request(req).await.succeeded().unwrap();
+    match user::Entity::find()
+        .filter(user::Column::Username.eq(claims.username.clone()))
+        .one(&**db)
+        .await
+    {
+        Ok(Some(user)) if user.role() >= min => request::Outcome::Success((claims, user)),
+        _ => request::Outcome::Forward(()),
+    }
+}
+
+/// A logged in moderator or admin
+pub struct Moderator {
+    pub claims: Claims,
+    pub user: user::Model,
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for Moderator {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        claims_with_role(req, Role::Moderator)
+            .await
+            .map(|(claims, user)| Moderator { claims, user })
+    }
+}
+
+impl<'a> OpenApiFromRequest<'a> for Moderator {
+    fn from_request_input(
+        gen: &mut rocket_okapi::gen::OpenApiGenerator,
+        name: String,
+        required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Claims::from_request_input(gen, name, required)
+    }
+}
+
+/// A logged in admin
+pub struct Admin {
+    pub claims: Claims,
+    pub user: user::Model,
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        claims_with_role(req, Role::Admin)
+            .await
+            .map(|(claims, user)| Admin { claims, user })
+    }
+}
+
+impl<'a> OpenApiFromRequest<'a> for Admin {
+    fn from_request_input(
+        gen: &mut rocket_okapi::gen::OpenApiGenerator,
+        name: String,
+        required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Claims::from_request_input(gen, name, required)
+    }
+}
+
+/// The `User-Agent` header, to tell sessions apart
+pub struct UserAgent(pub Option<String>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for UserAgent {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let agent = req.headers().get_one("User-Agent").map(str::to_owned);
+        request::Outcome::Success(UserAgent(agent))
+    }
+}
+
+impl<'a> OpenApiFromRequest<'a> for UserAgent {
+    fn from_request_input(
+        _gen: &mut rocket_okapi::gen::OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(RequestHeaderInput::None)
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use rocket::tokio::sync::{Notify, RwLock};
+use serde::Deserialize;
+use tictac::api::{Challenge, ChallengeData};
+
+/// Limits for bot accounts, from the `bots` table in `Rocket.toml`. Request
+/// rates for bots are with the others, in [`crate::rate_limit::RateLimits`].
+#[derive(Deserialize)]
+pub struct BotConfig {
+    /// Games a bot may be seated in at once
+    pub max_games: usize,
+    /// Whether bots may play rated games
+    pub rated: bool,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            max_games: 10,
+            rated: false,
+        }
+    }
+}
+
+/// Open challenges, kept in memory like the hub itself
+pub struct Challenges {
+    pub entries: RwLock<HashMap<u64, Challenge>>,
+    id_counter: RwLock<u64>,
+    /// Wakes up everyone waiting for new challenges
+    pub new: Notify,
+}
+
+impl Challenges {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+            id_counter: RwLock::new(0),
+            new: Notify::new(),
+        }
+    }
+
+    pub async fn add(&self, from: String, from_bot: bool, data: ChallengeData) -> u64 {
+        let mut counter = self.id_counter.write().await;
+        let id = *counter;
+        *counter += 1;
+        self.entries.write().await.insert(
+            id,
+            Challenge {
+                id,
+                from,
+                from_bot,
+                to: data.opponent,
+                rules: data.rules,
+                side: data.side,
+                rated: data.rated,
+            },
+        );
+        self.new.notify_waiters();
+        id
+    }
+
+    /// Challenges sent by or to `username`
+    pub async fn of(&self, username: &str) -> Vec<Challenge> {
+        let mut list: Vec<_> = self
+            .entries
+            .read()
+            .await
+            .values()
+            .filter(|c| c.from == username || c.to == username)
+            .cloned()
+            .collect();
+        list.sort_by_key(|c| c.id);
+        list
+    }
+
+    /// Follows a rename of `old`. Without `new` its challenges are dropped.
+    pub async fn rename(&self, old: &str, new: Option<&str>) {
+        let mut entries = self.entries.write().await;
+        match new {
+            Some(new) => {
+                for challenge in entries.values_mut() {
+                    if challenge.from == old {
+                        challenge.from = new.to_owned();
+                    }
+                    if challenge.to == old {
+                        challenge.to = new.to_owned();
+                    }
+                }
+            }
+            None => entries.retain(|_, c| c.from != old && c.to != old),
+        }
+    }
+}
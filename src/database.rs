@@ -1 +1,8 @@
+pub mod api_key;
+pub mod login_attempt;
+pub mod moderation_log;
+pub mod recovery_code;
+pub mod report;
+pub mod session;
 pub mod user;
+pub mod user_token;
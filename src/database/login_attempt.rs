@@ -0,0 +1,20 @@
+use sea_orm::entity::prelude::*;
+
+/// A login that went wrong, kept for auditing. Usernames are stored as
+/// typed, whether or not such an account exists.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "login_attempt")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub username: String,
+    pub ip: Option<String>,
+    /// `password`, `second_factor` or `throttled`
+    pub outcome: String,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
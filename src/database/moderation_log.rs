@@ -0,0 +1,24 @@
+use sea_orm::entity::prelude::*;
+
+/// One thing a moderator or admin did. Rows are never changed or removed.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "moderation_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub moderator_id: i32,
+    /// e.g. `ban`, `mute`, `abort_game` or `resolve_report`
+    pub action: String,
+    /// The account acted on
+    pub target_id: Option<i32>,
+    pub game_id: Option<i64>,
+    pub report_id: Option<i32>,
+    /// Reason, duration or new role
+    pub detail: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,29 @@
+use sea_orm::entity::prelude::*;
+
+/// A player's complaint about their opponent in a hub game
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "report")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    /// Either account may have been deleted since
+    pub reporter_id: i32,
+    pub reported_id: i32,
+    pub game_id: i64,
+    /// See [`tictac::api::ReportReason`]
+    pub reason: String,
+    pub comment: Option<String>,
+    /// The game as it stood, since the hub forgets games on restart
+    pub game_record: Option<String>,
+    /// See [`tictac::api::ReportStatus`]
+    pub status: String,
+    pub created_at: i64,
+    /// The moderator who resolved or dismissed it
+    pub handled_by: Option<i32>,
+    pub handled_at: Option<i64>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,6 +1,7 @@
 use rocket_okapi::JsonSchema;
 use sea_orm::entity::prelude::*;
 use serde::Serialize;
+use tictac::api::Role;
 
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, JsonSchema)]
 #[sea_orm(table_name = "user")]
@@ -10,6 +11,38 @@ pub struct Model {
     #[sea_orm(unique)]
     pub username: String,
     pub password: String,
+    pub is_bot: bool,
+    /// The account that created this bot
+    pub owner_id: Option<i32>,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    /// TOTP secret, encrypted with the server's TOTP key
+    pub totp_secret: Option<String>,
+    /// Set once a code from the enrolled secret was confirmed
+    pub totp_enabled: bool,
+    /// Time step of the last TOTP code accepted, so that no code works twice
+    pub totp_last_step: Option<i64>,
+    /// `user`, `moderator` or `admin`, see [`tictac::api::Role`]
+    pub role: String,
+    /// Unix timestamp the ban runs out at
+    pub banned_until: Option<i64>,
+    /// Unix timestamp the mute runs out at. Muted accounts can still play,
+    /// but can't challenge or report anyone.
+    pub muted_until: Option<i64>,
+}
+
+impl Model {
+    pub fn role(&self) -> Role {
+        Role::parse(&self.role).unwrap_or(Role::User)
+    }
+
+    pub fn is_banned(&self, now: i64) -> bool {
+        matches!(self.banned_until, Some(until) if until > now)
+    }
+
+    pub fn is_muted(&self, now: i64) -> bool {
+        matches!(self.muted_until, Some(until) if until > now)
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+
+/// Single-use tokens sent by mail. Only a hash of the token is kept, and the
+/// row goes away once the token is used.
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "user_token")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub user_id: i32,
+    /// `verify` or `reset`
+    pub purpose: String,
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    /// The address a verification token was sent to
+    pub email: Option<String>,
+    pub expires_at: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,10 +1,26 @@
 use std::collections::HashMap;
 
 use rocket::tokio::sync::RwLock;
-use rocket_okapi::JsonSchema;
-use serde::Serialize;
+use serde::Deserialize;
+pub use tictac::api::HubEntry;
+use tictac::{AnyGame, Game};
 
-use crate::tictac::{Cell, TicTacToeGame};
+/// Stands in for deleted accounts in the games they finished
+pub const DELETED_PLAYER: &str = "[deleted]";
+
+/// Limits for opening games, from the `games` table in `Rocket.toml`
+#[derive(Deserialize)]
+pub struct GameConfig {
+    /// Unfinished games an account may sit in and still open or import
+    /// another one. Moderators aren't held to it.
+    pub max_games: usize,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self { max_games: 20 }
+    }
+}
 
 pub struct Hub {
     pub entries: HashMap<u64, RwLock<HubEntry>>,
@@ -19,73 +35,52 @@ impl Hub {
         }
     }
 
-    pub fn new_game(&mut self, side: usize, criteria: usize) -> Option<u64> {
-        if side == 0 || side > 100 || criteria > side {
+    pub fn new_game(&mut self, game: AnyGame) -> Option<u64> {
+        if !game.is_playable() {
             return None;
         }
-        self.entries.insert(
-            self.id_counter,
-            RwLock::new(HubEntry {
-                game: TicTacToeGame::new(side, criteria),
-                x_player: None,
-                o_player: None,
-            }),
-        );
+        self.entries
+            .insert(self.id_counter, RwLock::new(HubEntry::new(game)));
         self.id_counter += 1;
         Some(self.id_counter - 1)
     }
-}
-
-#[derive(Serialize, Clone, JsonSchema)]
-pub struct HubEntry {
-    pub game: TicTacToeGame,
-    pub x_player: Option<String>,
-    pub o_player: Option<String>,
-}
 
-impl HubEntry {
-    fn get_player(&self, kind: Cell) -> Option<String> {
-        match kind {
-            Cell::X => self.x_player.clone(),
-            Cell::O => self.o_player.clone(),
+    /// Unfinished games `username` has a seat in
+    pub async fn active_games_of(&self, username: &str) -> usize {
+        let mut count = 0;
+        for entry in self.entries.values() {
+            let entry = entry.read().await;
+            if entry.side_of(username).is_some() && !entry.is_over() {
+                count += 1;
+            }
         }
+        count
     }
 
-    pub fn set_player(&mut self, kind: Cell, player: String) {
-        match kind {
-            Cell::X => {
-                if self.x_player.is_none() {
-                    self.x_player = Some(player)
+    /// Moves the seats of `old` over to `new`. Without `new` the account is
+    /// gone: its finished games keep a placeholder name, so their history
+    /// stays whole, and its seats in unfinished games become free.
+    pub async fn rename_player(&self, old: &str, new: Option<&str>) {
+        for entry in self.entries.values() {
+            let mut entry = entry.write().await;
+            let entry = &mut *entry;
+            let finished = entry.is_over();
+            for (seat, bot) in [
+                (&mut entry.x_player, &mut entry.x_bot),
+                (&mut entry.o_player, &mut entry.o_bot),
+            ] {
+                if seat.as_deref() != Some(old) {
+                    continue;
                 }
-            }
-            Cell::O => {
-                if self.o_player.is_none() {
-                    self.o_player = Some(player)
+                match new {
+                    Some(name) => *seat = Some(name.to_owned()),
+                    None if finished => *seat = Some(DELETED_PLAYER.to_owned()),
+                    None => {
+                        *seat = None;
+                        *bot = false;
+                    }
                 }
             }
         }
     }
-
-    pub fn is_a_player(&self, player: String) -> bool {
-        if let Some(p) = self.x_player.clone() {
-            p == player
-        } else if let Some(p) = self.o_player.clone() {
-            p == player
-        } else {
-            false
-        }
-    }
-
-    pub fn turn(&mut self, x: usize, y: usize, player_name: String) {
-        let next = self.game.current_player;
-        if next.is_none() {
-            return;
-        }
-        let next = next.unwrap();
-        let supposed_player = self.get_player(next);
-        if supposed_player.is_none() || supposed_player.unwrap() != player_name {
-            return;
-        }
-        self.game.turn(x, y);
-    }
 }
@@ -0,0 +1,133 @@
+use std::{collections::HashMap, fs};
+
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::{rand::SystemRandom, signature::Ed25519KeyPair};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tictac::api::{Jwk, JwkSet};
+
+/// One signing key, from the `jwt.keys` list in `Rocket.toml`
+#[derive(Deserialize)]
+pub struct KeyConfig {
+    pub kid: String,
+    /// PEM file of the Ed25519 private key
+    pub private: Option<String>,
+    /// PEM file of the public key, enough for keys that only verify
+    pub public: Option<String>,
+}
+
+/// The `jwt` table in `Rocket.toml`
+#[derive(Deserialize)]
+pub struct JwtConfig {
+    /// `kid` of the key new tokens are signed with
+    pub current: String,
+    pub keys: Vec<KeyConfig>,
+}
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`, followed by the 32 key bytes
+const ED25519_SPKI: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+fn read_pem(path: &str) -> Result<Vec<u8>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let body: String = text.lines().filter(|l| !l.starts_with("-----")).collect();
+    base64::decode(body.trim()).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Keys that sign and verify access tokens. Tokens name their key in the
+/// `kid` header, so tokens signed with a retired key stay valid as long as
+/// the key is still listed.
+pub struct Keys {
+    current: String,
+    encoding: EncodingKey,
+    decoding: HashMap<String, DecodingKey>,
+    jwks: JwkSet,
+}
+
+impl Keys {
+    pub fn load(config: &JwtConfig) -> Result<Self, String> {
+        let mut encoding = None;
+        let mut public_keys = Vec::new();
+        for key in &config.keys {
+            let public = match (&key.private, &key.public) {
+                (Some(path), _) => {
+                    let der = read_pem(path)?;
+                    let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+                        .map_err(|_| format!("{path}: not an Ed25519 private key"))?;
+                    if key.kid == config.current {
+                        encoding = Some(EncodingKey::from_ed_der(&der));
+                    }
+                    pair.public_key().as_ref().to_vec()
+                }
+                (None, Some(path)) => match read_pem(path)?.strip_prefix(&ED25519_SPKI[..]) {
+                    Some(x) if x.len() == 32 => x.to_vec(),
+                    _ => return Err(format!("{path}: not an Ed25519 public key")),
+                },
+                (None, None) => return Err(format!("key {} has no PEM file", key.kid)),
+            };
+            public_keys.push((key.kid.clone(), public));
+        }
+        let encoding = encoding
+            .ok_or_else(|| format!("no private key for the current key {}", config.current))?;
+        Ok(Self::new(config.current.clone(), encoding, public_keys))
+    }
+
+    /// A key made up on start, so development needs no setup. Tokens don't
+    /// survive a restart, but refresh tokens do.
+    pub fn ephemeral() -> Self {
+        let der = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).expect("no randomness");
+        let pair = Ed25519KeyPair::from_pkcs8(der.as_ref()).expect("a fresh key parses");
+        Self::new(
+            "dev".to_owned(),
+            EncodingKey::from_ed_der(der.as_ref()),
+            vec![("dev".to_owned(), pair.public_key().as_ref().to_vec())],
+        )
+    }
+
+    fn new(current: String, encoding: EncodingKey, public_keys: Vec<(String, Vec<u8>)>) -> Self {
+        let jwks = JwkSet {
+            keys: public_keys
+                .iter()
+                .map(|(kid, x)| Jwk {
+                    kty: "OKP".to_owned(),
+                    crv: "Ed25519".to_owned(),
+                    alg: "EdDSA".to_owned(),
+                    usage: "sig".to_owned(),
+                    kid: kid.clone(),
+                    x: base64::encode_config(x, base64::URL_SAFE_NO_PAD),
+                })
+                .collect(),
+        };
+        let decoding = public_keys
+            .into_iter()
+            .map(|(kid, x)| (kid, DecodingKey::from_ed_der(&x)))
+            .collect();
+        Self {
+            current,
+            encoding,
+            decoding,
+            jwks,
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Option<String> {
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(self.current.clone());
+        encode(&header, claims, &self.encoding).ok()
+    }
+
+    /// The claims of a token signed with any of the keys
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Option<T> {
+        let kid = decode_header(token).ok()?.kid?;
+        let key = self.decoding.get(&kid)?;
+        decode::<T>(token, key, &Validation::new(Algorithm::EdDSA))
+            .ok()
+            .map(|data| data.claims)
+    }
+
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+
+use chrono::Utc;
+use lettre::{
+    transport::smtp::authentication::Credentials, AsyncSmtpTransport, AsyncTransport, Message,
+    Tokio1Executor,
+};
+use rocket::{async_trait, tokio::fs};
+use serde::Deserialize;
+
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Sends mail, so the rest of the server doesn't care how
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, mail: Mail) -> Result<(), String>;
+}
+
+#[derive(Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: String,
+    pub password: String,
+}
+
+/// The `mail` table in `Rocket.toml`
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct MailConfig {
+    pub from: String,
+    /// Where the links in mails point to
+    pub base_url: String,
+    /// Where mails are written to without `smtp`
+    pub dir: String,
+    pub smtp: Option<SmtpConfig>,
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        Self {
+            from: "Tic-tac-toe <noreply@localhost>".to_owned(),
+            base_url: "http://localhost:8000".to_owned(),
+            dir: "mail".to_owned(),
+            smtp: None,
+        }
+    }
+}
+
+impl MailConfig {
+    /// SMTP when configured, otherwise files in `dir`
+    pub fn mailer(&self) -> Box<dyn Mailer> {
+        match &self.smtp {
+            Some(smtp) => Box::new(SmtpMailer::new(&self.from, smtp)),
+            None => Box::new(FileMailer {
+                from: self.from.clone(),
+                dir: PathBuf::from(&self.dir),
+            }),
+        }
+    }
+}
+
+/// Writes each mail to a file instead of sending it, for development and
+/// tests
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, mail: Mail) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            Utc::now().timestamp_millis(),
+            mail.to
+                .replace(|c: char| !c.is_alphanumeric() && c != '@', "_")
+        ));
+        let text = format!(
+            "From: {}\nTo: {}\nSubject: {}\n\n{}\n",
+            self.from, mail.to, mail.subject, mail.body
+        );
+        fs::write(&path, text).await.map_err(|e| e.to_string())?;
+        log::info!("Mail to {} written to {}", mail.to, path.display());
+        Ok(())
+    }
+}
+
+pub struct SmtpMailer {
+    from: String,
+    transport: Result<AsyncSmtpTransport<Tokio1Executor>, String>,
+}
+
+impl SmtpMailer {
+    pub fn new(from: &str, config: &SmtpConfig) -> Self {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)
+            .map(|builder| {
+                let builder = builder.credentials(Credentials::new(
+                    config.username.clone(),
+                    config.password.clone(),
+                ));
+                match config.port {
+                    Some(port) => builder.port(port),
+                    None => builder,
+                }
+                .build()
+            })
+            .map_err(|e| e.to_string());
+        Self {
+            from: from.to_owned(),
+            transport,
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, mail: Mail) -> Result<(), String> {
+        let transport = self.transport.as_ref()?;
+        let message = Message::builder()
+            .from(self.from.parse().map_err(|e| format!("{e}"))?)
+            .to(mail.to.parse().map_err(|e| format!("{e}"))?)
+            .subject(mail.subject)
+            .body(mail.body)
+            .map_err(|e| e.to_string())?;
+        transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
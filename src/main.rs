@@ -1,7 +1,12 @@
 // #![deny(warnings)]
 
-use auth::Secret;
-use hub::Hub;
+use auth::{AdminConfig, SessionConfig};
+use challenges::{BotConfig, Challenges};
+use hub::{GameConfig, Hub};
+use keys::{JwtConfig, Keys};
+use mailer::MailConfig;
+use rate_limit::{RateLimiter, RateLimits};
+use render::Renderer;
 use rocket::{
     launch,
     tokio::sync::{Mutex, RwLock},
@@ -9,26 +14,44 @@ use rocket::{
 use rocket_cors::{AllowedOrigins, CorsOptions};
 use rocket_okapi::swagger_ui::{make_swagger_ui, SwaggerUIConfig};
 use sea_orm::Database;
+use throttle::{LoginLimits, LoginThrottle, ProxyConfig};
+use tictac::TicTacToeGame;
+use totp::{SecretBox, TotpConfig};
 
 mod api_routes;
 mod auth;
+mod challenges;
 pub mod database;
 mod hub;
-mod tictac;
+mod keys;
+mod mailer;
+mod rate_limit;
+mod render;
+mod throttle;
+mod totp;
 #[cfg(debug_assertions)]
-fn get_secret() -> String {
-    "secret".to_owned()
+fn default_keys() -> Keys {
+    Keys::ephemeral()
 }
 
 #[cfg(not(debug_assertions))]
-fn get_secret() -> String {
-    std::env::var("SECRET").unwrap()
+fn default_keys() -> Keys {
+    panic!("no signing keys: set `jwt.current` and `jwt.keys` in Rocket.toml")
+}
+
+#[cfg(debug_assertions)]
+fn default_totp_key() -> SecretBox {
+    SecretBox::new(b"development key, not for release")
+}
+
+#[cfg(not(debug_assertions))]
+fn default_totp_key() -> SecretBox {
+    panic!("no TOTP key: set `totp.key` in Rocket.toml")
 }
 
 #[launch]
 async fn rocket() -> _ {
     let db = Database::connect("sqlite://root.db").await.unwrap();
-    let secret = get_secret();
 
     let allowed_origins = AllowedOrigins::All;
     let cors = CorsOptions {
@@ -38,12 +61,53 @@ async fn rocket() -> _ {
     .to_cors()
     .unwrap();
 
-    rocket::build()
-        .manage(Mutex::new(tictac::TicTacToeGame::new(3, 3)))
+    let rocket = rocket::build();
+    let keys = match rocket.figment().extract_inner::<JwtConfig>("jwt") {
+        Ok(config) => Keys::load(&config).unwrap_or_else(|e| panic!("bad signing keys: {e}")),
+        Err(_) => default_keys(),
+    };
+    let bots: BotConfig = rocket.figment().extract_inner("bots").unwrap_or_default();
+    let games: GameConfig = rocket.figment().extract_inner("games").unwrap_or_default();
+    let totp: TotpConfig = rocket.figment().extract_inner("totp").unwrap_or_default();
+    let secrets = match &totp.key {
+        Some(key) => SecretBox::from_hex(key).expect("`totp.key` must be 64 hex digits"),
+        None => default_totp_key(),
+    };
+    let proxy: ProxyConfig = rocket.figment().extract_inner("proxy").unwrap_or_default();
+    let login_limits: LoginLimits = rocket
+        .figment()
+        .extract_inner("login_limits")
+        .unwrap_or_default();
+    let rate_limits: RateLimits = rocket
+        .figment()
+        .extract_inner("rate_limits")
+        .unwrap_or_default();
+    let admins: AdminConfig = rocket.figment().extract_inner("admin").unwrap_or_default();
+    auth::promote_admins(&db, &admins).await;
+    let mail: MailConfig = rocket.figment().extract_inner("mail").unwrap_or_default();
+    let sessions: SessionConfig = rocket
+        .figment()
+        .extract_inner("sessions")
+        .unwrap_or_default();
+
+    rocket
+        .manage(Mutex::new(TicTacToeGame::new(3, 3)))
         .manage(RwLock::new(Hub::new()))
         .manage(db)
-        .manage(Secret(secret))
+        .manage(keys)
+        .manage(Renderer::new())
+        .manage(Challenges::new())
+        .manage(bots)
+        .manage(games)
+        .manage(sessions)
+        .manage(mail.mailer())
+        .manage(mail)
+        .manage(totp)
+        .manage(secrets)
+        .manage(LoginThrottle::new(login_limits))
+        .manage(proxy)
         .mount("/", api_routes::routes())
+        .mount("/", rate_limit::routes())
         .mount(
             "/docs/",
             make_swagger_ui(&SwaggerUIConfig {
@@ -51,5 +115,6 @@ async fn rocket() -> _ {
                 ..Default::default()
             }),
         )
+        .attach(RateLimiter::new(rate_limits))
         .attach(cors)
 }
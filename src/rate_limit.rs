@@ -0,0 +1,370 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use rocket::{
+    async_trait,
+    fairing::{Fairing, Info, Kind},
+    get,
+    http::{uri::Origin, Header, Method},
+    request::{self, FromRequest},
+    routes,
+    tokio::sync::Mutex,
+    Data, Request, Response, Route,
+};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+
+use crate::{
+    auth::{bot_claims, hash_token, Claims, API_KEY_PREFIX},
+    keys::Keys,
+    throttle::{client_ip, TooManyRequests},
+};
+
+/// A token bucket: `burst` requests at once, refilled at `per_minute`
+#[derive(Clone, Copy, Deserialize)]
+pub struct Rate {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+/// Request rates, from the `rate_limits` table in `Rocket.toml`
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct RateLimits {
+    /// Per IP address, for requests without a login
+    pub anonymous: Rate,
+    pub user: Rate,
+    /// Per API key
+    pub bot: Rate,
+    /// Listings, rendering and imports, counted apart from everything else
+    pub heavy: Rate,
+    /// The game list and single games, which clients poll
+    pub polling: Rate,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            anonymous: Rate {
+                burst: 30,
+                per_minute: 60,
+            },
+            user: Rate {
+                burst: 60,
+                per_minute: 120,
+            },
+            bot: Rate {
+                burst: 120,
+                per_minute: 600,
+            },
+            heavy: Rate {
+                burst: 10,
+                per_minute: 20,
+            },
+            polling: Rate {
+                burst: 20,
+                per_minute: 120,
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Client {
+    Ip(Option<IpAddr>),
+    User(String),
+    /// Hash of the API key
+    Bot(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Class {
+    Normal,
+    Heavy,
+    Polling,
+}
+
+/// What a request got from its bucket
+#[derive(Clone, Copy)]
+struct Decision {
+    limit: u32,
+    remaining: u32,
+    /// Seconds until the bucket is full again
+    reset: u64,
+    retry_after: Option<u64>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn take(&mut self, rate: Rate, now: Instant) -> Decision {
+        let per_second = rate.per_minute.max(1) as f64 / 60.0;
+        let burst = rate.burst as f64;
+        let refill = now.duration_since(self.updated).as_secs_f64() * per_second;
+        self.tokens = (self.tokens + refill).min(burst);
+        self.updated = now;
+        let retry_after = if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(((1.0 - self.tokens) / per_second).ceil() as u64)
+        };
+        Decision {
+            limit: rate.burst,
+            remaining: self.tokens as u32,
+            reset: ((burst - self.tokens) / per_second).ceil() as u64,
+            retry_after,
+        }
+    }
+}
+
+struct Buckets {
+    entries: HashMap<(Client, Class), Bucket>,
+    pruned: Instant,
+}
+
+/// Where requests over their limit are sent instead
+const LIMITED_PATH: &str = "/rate-limited";
+
+/// Token buckets per user, API key or IP address, for every route
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<Buckets>,
+    /// Idle time after which any bucket is full, and can be forgotten
+    refill: Duration,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        let rates = [
+            limits.anonymous,
+            limits.user,
+            limits.bot,
+            limits.heavy,
+            limits.polling,
+        ];
+        let refill = rates
+            .iter()
+            .map(|rate| rate.burst as u64 * 60 / rate.per_minute.max(1) as u64 + 1)
+            .max()
+            .unwrap_or_default();
+        Self {
+            limits,
+            buckets: Mutex::new(Buckets {
+                entries: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+            refill: Duration::from_secs(refill),
+        }
+    }
+
+    fn rate(&self, client: &Client, class: Class) -> Rate {
+        match (class, client) {
+            (Class::Heavy, _) => self.limits.heavy,
+            (Class::Polling, _) => self.limits.polling,
+            (Class::Normal, Client::Ip(_)) => self.limits.anonymous,
+            (Class::Normal, Client::User(_)) => self.limits.user,
+            (Class::Normal, Client::Bot(_)) => self.limits.bot,
+        }
+    }
+
+    async fn take(&self, client: Client, class: Class) -> Decision {
+        let rate = self.rate(&client, class);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().await;
+        if now.duration_since(buckets.pruned) > self.refill {
+            let refill = self.refill;
+            buckets
+                .entries
+                .retain(|_, bucket| now.duration_since(bucket.updated) < refill);
+            buckets.pruned = now;
+        }
+        buckets
+            .entries
+            .entry((client, class))
+            .or_insert(Bucket {
+                tokens: rate.burst as f64,
+                updated: now,
+            })
+            .take(rate, now)
+    }
+}
+
+/// Who a request counts against. Access tokens are only checked for their
+/// signature here, the routes' guards do the rest. API keys have to belong
+/// to a bot, or anyone could make up keys for buckets of their own.
+async fn client_of(req: &Request<'_>) -> Client {
+    let token = req
+        .headers()
+        .get_one("Authorization")
+        .and_then(|header| header.strip_prefix("Bearer "));
+    let anonymous = || Client::Ip(client_ip(req));
+    match token {
+        Some(key) if key.starts_with(API_KEY_PREFIX) => {
+            let bot = match req.rocket().state::<DatabaseConnection>() {
+                Some(db) => bot_claims(db, key).await,
+                None => None,
+            };
+            match bot {
+                Some(_) => Client::Bot(hash_token(key)),
+                None => anonymous(),
+            }
+        }
+        Some(token) => match req
+            .rocket()
+            .state::<Keys>()
+            .and_then(|keys| keys.decode::<Claims>(token))
+        {
+            Some(claims) => Client::User(claims.username),
+            None => anonymous(),
+        },
+        None => anonymous(),
+    }
+}
+
+fn class_of(req: &Request<'_>) -> Class {
+    let path = req.uri().path();
+    let path = path.as_str();
+    let game = matches!(
+        path.strip_prefix("/games/").map(str::parse::<u64>),
+        Some(Ok(_))
+    );
+    match req.method() {
+        Method::Get if path == "/games" || game => Class::Polling,
+        Method::Get
+            if path.ends_with("/export")
+                || [".svg", ".png", ".gif"]
+                    .iter()
+                    .any(|ext| path.ends_with(ext)) =>
+        {
+            Class::Heavy
+        }
+        Method::Post if path == "/games/import" => Class::Heavy,
+        _ => Class::Normal,
+    }
+}
+
+#[async_trait]
+impl Fairing for RateLimiter {
+    fn info(&self) -> Info {
+        Info {
+            name: "Rate limiter",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        let decision = self.take(client_of(req).await, class_of(req)).await;
+        req.local_cache(|| Some(decision));
+        if decision.retry_after.is_some() {
+            // Fairings can't answer requests themselves, so the request is
+            // rerouted to one that answers 429
+            req.set_method(Method::Get);
+            req.set_uri(Origin::parse(LIMITED_PATH).unwrap());
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if let Some(decision) = req.local_cache(|| None::<Decision>) {
+            res.set_header(Header::new("X-RateLimit-Limit", decision.limit.to_string()));
+            res.set_header(Header::new(
+                "X-RateLimit-Remaining",
+                decision.remaining.to_string(),
+            ));
+            res.set_header(Header::new("X-RateLimit-Reset", decision.reset.to_string()));
+        }
+    }
+}
+
+#[async_trait]
+impl<'r> FromRequest<'r> for Decision {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        match req.local_cache(|| None::<Decision>) {
+            Some(decision) if decision.retry_after.is_some() => {
+                request::Outcome::Success(*decision)
+            }
+            _ => request::Outcome::Forward(()),
+        }
+    }
+}
+
+#[get("/rate-limited")]
+fn rate_limited(decision: Decision) -> TooManyRequests {
+    TooManyRequests(decision.retry_after.unwrap_or(1) as i64)
+}
+
+/// The route limited requests end up at; it is not part of the API
+pub fn routes() -> Vec<Route> {
+    routes![rate_limited]
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{Bucket, Rate};
+
+    const RATE: Rate = Rate {
+        burst: 3,
+        per_minute: 60,
+    };
+
+    #[test]
+    fn a_full_bucket_takes_its_burst_then_refuses() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: RATE.burst as f64,
+            updated: start,
+        };
+        let decisions: Vec<_> = (0..4).map(|_| bucket.take(RATE, start)).collect();
+        let remaining: Vec<_> = decisions.iter().map(|d| d.remaining).collect();
+        assert_eq!(remaining, [2, 1, 0, 0]);
+        assert!(decisions[..3].iter().all(|d| d.retry_after.is_none()));
+        assert_eq!(decisions[3].retry_after, Some(1));
+        assert_eq!(decisions[3].limit, 3);
+        assert_eq!(decisions[3].reset, 3);
+    }
+
+    #[test]
+    fn buckets_refill_at_their_rate_up_to_the_burst() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            updated: start,
+        };
+        let half = start + Duration::from_millis(500);
+        assert_eq!(bucket.take(RATE, half).retry_after, Some(1));
+        let second = start + Duration::from_secs(1);
+        let decision = bucket.take(RATE, second);
+        assert_eq!(decision.retry_after, None);
+        assert_eq!(decision.remaining, 0);
+
+        let idle = second + Duration::from_secs(60);
+        let decision = bucket.take(RATE, idle);
+        assert_eq!(decision.retry_after, None);
+        assert_eq!(decision.remaining, 2);
+        assert_eq!(decision.reset, 1);
+    }
+
+    #[test]
+    fn a_zero_rate_still_refills_slowly() {
+        let rate = Rate {
+            burst: 1,
+            per_minute: 0,
+        };
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            updated: start,
+        };
+        assert_eq!(bucket.take(rate, start).retry_after, Some(60));
+    }
+}
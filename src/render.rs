@@ -0,0 +1,229 @@
+use std::{fmt::Write, sync::Arc};
+
+use tictac::{notation, AnyGame, Cell, Game};
+
+/// Boards are scaled to roughly this many pixels across
+const BOARD_PX: usize = 600;
+const MARGIN: usize = 10;
+const HEADER: usize = 30;
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Draws the board with the player names on top, the last move marked and the
+/// winning line crossed out
+pub fn board_svg(game: &AnyGame, x_player: Option<&str>, o_player: Option<&str>) -> String {
+    let size = game.size();
+    let cell = (BOARD_PX / size).clamp(6, 60);
+    let board = cell * size;
+    let width = board + 2 * MARGIN;
+    let height = board + 2 * MARGIN + HEADER;
+    let stroke = (cell / 12).max(1);
+    // Top left corner of a cell
+    let corner = |x: usize, y: usize| (MARGIN + y * cell, HEADER + MARGIN + x * cell);
+    let center = |x: usize, y: usize| {
+        let (left, top) = corner(x, y);
+        (left + cell / 2, top + cell / 2)
+    };
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    let _ = write!(
+        svg,
+        r##"<rect width="{width}" height="{height}" fill="#ffffff"/>"##
+    );
+    let name = |player: Option<&str>| escape(player.unwrap_or("(open)"));
+    let _ = write!(
+        svg,
+        r##"<text x="{MARGIN}" y="20" font-family="sans-serif" font-size="14" fill="#c0392b">X: {}</text>"##,
+        name(x_player)
+    );
+    let _ = write!(
+        svg,
+        r##"<text x="{}" y="20" font-family="sans-serif" font-size="14" fill="#2471a3" text-anchor="end">O: {}</text>"##,
+        width - MARGIN,
+        name(o_player)
+    );
+
+    if let Some(last) = game.history().last() {
+        let (left, top) = corner(last.x, last.y);
+        let _ = write!(
+            svg,
+            r##"<rect x="{left}" y="{top}" width="{cell}" height="{cell}" fill="#fff3a0"/>"##
+        );
+    }
+
+    for i in 0..=size {
+        let offset = i * cell;
+        let _ = write!(
+            svg,
+            r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#888888" stroke-width="1"/>"##,
+            MARGIN + offset,
+            HEADER + MARGIN,
+            MARGIN + offset,
+            HEADER + MARGIN + board
+        );
+        let _ = write!(
+            svg,
+            r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#888888" stroke-width="1"/>"##,
+            MARGIN,
+            HEADER + MARGIN + offset,
+            MARGIN + board,
+            HEADER + MARGIN + offset
+        );
+    }
+
+    let pad = cell / 5;
+    for x in 0..size {
+        for y in 0..size {
+            let (left, top) = corner(x, y);
+            let (cx, cy) = center(x, y);
+            match game {
+                AnyGame::Classic(game) => match game.field[x][y] {
+                    Some(Cell::X) => {
+                        let _ = write!(
+                            svg,
+                            r##"<path d="M{} {}L{} {}M{} {}L{} {}" stroke="#c0392b" stroke-width="{stroke}" stroke-linecap="round"/>"##,
+                            left + pad,
+                            top + pad,
+                            left + cell - pad,
+                            top + cell - pad,
+                            left + cell - pad,
+                            top + pad,
+                            left + pad,
+                            top + cell - pad
+                        );
+                    }
+                    Some(Cell::O) => {
+                        let _ = write!(
+                            svg,
+                            r##"<circle cx="{cx}" cy="{cy}" r="{}" fill="none" stroke="#2471a3" stroke-width="{stroke}"/>"##,
+                            cell / 2 - pad
+                        );
+                    }
+                    None => {}
+                },
+                AnyGame::Numerical(game) => {
+                    if let Some(number) = game.field[x][y] {
+                        let color = if number % 2 == 1 {
+                            "#c0392b"
+                        } else {
+                            "#2471a3"
+                        };
+                        let _ = write!(
+                            svg,
+                            r#"<text x="{cx}" y="{cy}" font-family="sans-serif" font-size="{}" fill="{color}" text-anchor="middle" dominant-baseline="central">{number}</text>"#,
+                            cell / 2
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(line) = game.winning_line() {
+        if let (Some(&(x1, y1)), Some(&(x2, y2))) = (line.first(), line.last()) {
+            let (x1, y1) = center(x1, y1);
+            let (x2, y2) = center(x2, y2);
+            let _ = write!(
+                svg,
+                r##"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="#27ae60" stroke-opacity="0.7" stroke-width="{}" stroke-linecap="round"/>"##,
+                (cell / 4).max(2)
+            );
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Longest replay, in frames. Longer games skip some of their moves.
+const MAX_FRAMES: usize = 200;
+
+/// Turns SVG boards into bitmaps. Fonts are looked up once on startup, so keep
+/// a single renderer in Rocket's managed state.
+/// Cheap to clone, so it can be handed to blocking tasks
+#[derive(Clone)]
+pub struct Renderer {
+    options: Arc<usvg::Options>,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        let mut options = usvg::Options::default();
+        options.fontdb.load_system_fonts();
+        Self {
+            options: Arc::new(options),
+        }
+    }
+
+    pub fn rasterize(&self, svg: &str) -> Option<tiny_skia::Pixmap> {
+        let tree = usvg::Tree::from_str(svg, &self.options.to_ref()).ok()?;
+        let size = tree.svg_node().size.to_screen_size();
+        let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())?;
+        resvg::render(
+            &tree,
+            usvg::FitTo::Original,
+            tiny_skia::Transform::default(),
+            pixmap.as_mut(),
+        )?;
+        Some(pixmap)
+    }
+
+    pub fn png(&self, svg: &str) -> Option<Vec<u8>> {
+        self.rasterize(svg)?.encode_png().ok()
+    }
+
+    /// One frame per move of the finished game (every few moves past
+    /// `MAX_FRAMES`), then the final position with the winning line. `delay`
+    /// is in hundredths of a second. Frames are encoded as they are drawn.
+    pub fn replay_gif(
+        &self,
+        game: &AnyGame,
+        x_player: Option<&str>,
+        o_player: Option<&str>,
+        delay: u16,
+    ) -> Option<Vec<u8>> {
+        // Games imported from a position only have where they ended up
+        let (mut replay, moves) = if notation::has_full_history(game) {
+            (notation::restart(game), game.history())
+        } else {
+            (game.clone(), &[][..])
+        };
+        let step = (moves.len() + MAX_FRAMES) / MAX_FRAMES;
+
+        let mut pixmap = self.rasterize(&board_svg(&replay, x_player, o_player))?;
+        let (width, height) = (pixmap.width() as u16, pixmap.height() as u16);
+        let mut gif = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut gif, width, height, &[]).ok()?;
+            encoder.set_repeat(gif::Repeat::Infinite).ok()?;
+            let mut shown = 0;
+            loop {
+                let last = shown == moves.len();
+                let mut frame = gif::Frame::from_rgba_speed(width, height, pixmap.data_mut(), 10);
+                // Linger on the final position before looping
+                frame.delay = if last { delay.saturating_mul(4) } else { delay };
+                encoder.write_frame(&frame).ok()?;
+                if last {
+                    break;
+                }
+                let next = (shown + step).min(moves.len());
+                for mv in &moves[shown..next] {
+                    replay.apply(mv);
+                }
+                shown = next;
+                pixmap = self.rasterize(&board_svg(&replay, x_player, o_player))?;
+            }
+        }
+        Some(gif)
+    }
+}
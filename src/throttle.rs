@@ -0,0 +1,315 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use rocket::{
+    async_trait,
+    http::Status,
+    request::{self, FromRequest},
+    response::{self, Responder},
+    tokio::sync::Mutex,
+    Request, Response,
+};
+use rocket_okapi::{
+    gen::OpenApiGenerator,
+    okapi::openapi3::{RefOr, Response as OpenApiResponse, Responses},
+    request::{OpenApiFromRequest, RequestHeaderInput},
+    response::OpenApiResponderInner,
+};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use serde::Deserialize;
+
+/// Limits on login attempts, from the `login_limits` table in `Rocket.toml`
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct LoginLimits {
+    /// Failures from one IP address before delays start
+    pub ip_free_attempts: u32,
+    /// Failures for one username before delays start
+    pub user_free_attempts: u32,
+    /// First delay in seconds, doubling with every further failure
+    pub base_delay: i64,
+    pub max_delay: i64,
+    /// Failures for one username that lock it
+    pub lockout_after: u32,
+    pub lockout_minutes: i64,
+    /// Failures are forgotten after this long without another one
+    pub forget_minutes: i64,
+}
+
+impl Default for LoginLimits {
+    fn default() -> Self {
+        Self {
+            ip_free_attempts: 10,
+            user_free_attempts: 3,
+            base_delay: 1,
+            max_delay: 300,
+            lockout_after: 10,
+            lockout_minutes: 15,
+            forget_minutes: 60,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Key {
+    Ip(IpAddr),
+    User(String),
+}
+
+struct Failures {
+    count: u32,
+    /// Unix timestamp of the latest failure
+    last: i64,
+}
+
+/// Failed logins per IP address and per username, kept in memory
+pub struct LoginThrottle {
+    limits: LoginLimits,
+    failures: Mutex<HashMap<Key, Failures>>,
+}
+
+impl LoginThrottle {
+    pub fn new(limits: LoginLimits) -> Self {
+        Self {
+            limits,
+            failures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn backoff(&self, failures: &Failures, free: u32) -> i64 {
+        if failures.count < free {
+            return 0;
+        }
+        let doublings = (failures.count - free).min(30);
+        (self.limits.base_delay << doublings).min(self.limits.max_delay)
+    }
+
+    /// Seconds `ip` has to wait before trying `username` again, if any
+    pub async fn check(&self, ip: Option<IpAddr>, username: &str, now: i64) -> Option<i64> {
+        let failures = self.failures.lock().await;
+        let mut until = 0;
+        if let Some(f) = failures.get(&Key::User(username.to_owned())) {
+            until = if f.count >= self.limits.lockout_after {
+                f.last + self.limits.lockout_minutes * 60
+            } else {
+                f.last + self.backoff(f, self.limits.user_free_attempts)
+            };
+        }
+        if let Some(f) = ip.and_then(|ip| failures.get(&Key::Ip(ip))) {
+            until = until.max(f.last + self.backoff(f, self.limits.ip_free_attempts));
+        }
+        (until > now).then_some(until - now)
+    }
+
+    pub async fn failed(&self, ip: Option<IpAddr>, username: &str, now: i64) {
+        let mut failures = self.failures.lock().await;
+        let keep = self.limits.forget_minutes.max(self.limits.lockout_minutes) * 60;
+        failures.retain(|_, f| now - f.last < keep);
+        let keys = ip
+            .map(Key::Ip)
+            .into_iter()
+            .chain([Key::User(username.to_owned())]);
+        for key in keys {
+            let f = failures.entry(key).or_insert(Failures {
+                count: 0,
+                last: now,
+            });
+            f.count += 1;
+            f.last = now;
+        }
+    }
+
+    /// Forgets the failures for `username`, but not those of the IP address,
+    /// or one valid account would clear the way for guessing others
+    pub async fn succeeded(&self, username: &str) {
+        self.failures
+            .lock()
+            .await
+            .remove(&Key::User(username.to_owned()));
+    }
+}
+
+pub const PASSWORD: &str = "password";
+pub const SECOND_FACTOR: &str = "second_factor";
+pub const THROTTLED: &str = "throttled";
+
+/// Records a failed login attempt for auditing
+pub async fn audit(db: &DatabaseConnection, username: &str, ip: Option<IpAddr>, outcome: &str) {
+    use crate::database::login_attempt;
+    let row = login_attempt::ActiveModel {
+        username: Set(username.to_owned()),
+        ip: Set(ip.map(|ip| ip.to_string())),
+        outcome: Set(outcome.to_owned()),
+        created_at: Set(chrono::Utc::now().timestamp()),
+        ..Default::default()
+    };
+    // Auditing must not get in the way of logging in
+    let _ = row.insert(db).await;
+}
+
+/// The `proxy` table in `Rocket.toml`
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// Header a reverse proxy puts the client's address in. Anyone can send
+    /// it, so only set this behind a proxy that overwrites it.
+    pub ip_header: Option<String>,
+}
+
+/// The address the request came from: the peer's, or the one in the
+/// configured proxy header
+pub fn client_ip(req: &Request<'_>) -> Option<IpAddr> {
+    let header = req
+        .rocket()
+        .state::<ProxyConfig>()
+        .and_then(|config| config.ip_header.as_deref());
+    header
+        .and_then(|header| header_ip(req.headers().get_one(header)?))
+        .or_else(|| req.remote().map(|addr| addr.ip()))
+}
+
+/// The address in a proxy header. Headers like `X-Forwarded-For` list every
+/// hop, and only the last one was written by our own proxy.
+fn header_ip(value: &str) -> Option<IpAddr> {
+    value.rsplit(',').next()?.trim().parse().ok()
+}
+
+/// The address the request came from, see [`client_ip`]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<'r> FromRequest<'r> for ClientIp {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(ClientIp(client_ip(req)))
+    }
+}
+
+impl<'a> OpenApiFromRequest<'a> for ClientIp {
+    fn from_request_input(
+        _gen: &mut OpenApiGenerator,
+        _name: String,
+        _required: bool,
+    ) -> rocket_okapi::Result<RequestHeaderInput> {
+        Ok(RequestHeaderInput::None)
+    }
+}
+
+/// `429 Too Many Requests`, with the seconds to wait in `Retry-After`
+pub struct TooManyRequests(pub i64);
+
+impl<'r> Responder<'r, 'static> for TooManyRequests {
+    fn respond_to(self, _req: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .status(Status::TooManyRequests)
+            .raw_header("Retry-After", self.0.to_string())
+            .ok()
+    }
+}
+
+impl OpenApiResponderInner for TooManyRequests {
+    fn responses(_gen: &mut OpenApiGenerator) -> rocket_okapi::Result<Responses> {
+        let mut responses = Responses::default();
+        responses.responses.insert(
+            "429".to_owned(),
+            RefOr::Object(OpenApiResponse {
+                description: "Too many requests, retry after `Retry-After` seconds".to_owned(),
+                ..Default::default()
+            }),
+        );
+        Ok(responses)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use super::{header_ip, LoginLimits, LoginThrottle};
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, last)))
+    }
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle::new(LoginLimits {
+            ip_free_attempts: 5,
+            user_free_attempts: 2,
+            base_delay: 1,
+            max_delay: 8,
+            lockout_after: 8,
+            lockout_minutes: 15,
+            forget_minutes: 60,
+        })
+    }
+
+    #[rocket::async_test]
+    async fn delays_double_after_the_free_attempts_up_to_the_cap() {
+        let throttle = throttle();
+        let mut waits = Vec::new();
+        for _ in 0..7 {
+            throttle.failed(ip(1), "alice", NOW).await;
+            waits.push(throttle.check(ip(1), "alice", NOW).await);
+        }
+        let expected = [None, Some(1), Some(2), Some(4), Some(8), Some(8), Some(8)];
+        assert_eq!(waits, expected);
+        // The wait runs from the latest failure
+        assert_eq!(throttle.check(ip(1), "alice", NOW + 5).await, Some(3));
+        assert_eq!(throttle.check(ip(1), "alice", NOW + 8).await, None);
+    }
+
+    #[rocket::async_test]
+    async fn too_many_failures_lock_the_username_from_everywhere() {
+        let throttle = throttle();
+        for i in 0..8 {
+            throttle.failed(ip(i), "alice", NOW).await;
+        }
+        assert_eq!(throttle.check(ip(100), "alice", NOW).await, Some(15 * 60));
+        assert_eq!(throttle.check(None, "alice", NOW + 60).await, Some(14 * 60));
+        assert_eq!(throttle.check(ip(100), "bob", NOW).await, None);
+    }
+
+    #[rocket::async_test]
+    async fn one_address_guessing_many_usernames_is_slowed_down() {
+        let throttle = throttle();
+        for i in 0..5 {
+            throttle.failed(ip(1), &format!("user{i}"), NOW).await;
+        }
+        assert_eq!(throttle.check(ip(1), "someone", NOW).await, Some(1));
+        assert_eq!(throttle.check(ip(2), "someone", NOW).await, None);
+        assert_eq!(throttle.check(None, "someone", NOW).await, None);
+    }
+
+    #[rocket::async_test]
+    async fn failures_are_forgotten() {
+        let throttle = throttle();
+        for _ in 0..8 {
+            throttle.failed(ip(1), "alice", NOW).await;
+        }
+        throttle.succeeded("alice").await;
+        // A login clears the username, but not the address
+        assert_eq!(throttle.check(ip(2), "alice", NOW).await, None);
+        assert_eq!(throttle.check(ip(1), "bob", NOW).await, Some(8));
+
+        for _ in 0..8 {
+            throttle.failed(ip(1), "alice", NOW).await;
+        }
+        // Old failures go once the next failure comes in after a quiet hour
+        let later = NOW + 60 * 60;
+        throttle.failed(ip(3), "carol", later).await;
+        assert_eq!(throttle.check(ip(1), "alice", later).await, None);
+        throttle.failed(ip(1), "alice", later).await;
+        assert_eq!(throttle.check(ip(1), "alice", later).await, None);
+    }
+
+    #[test]
+    fn proxy_header_lists() {
+        assert_eq!(header_ip(" 192.0.2.1 "), ip(1));
+        assert_eq!(header_ip("198.51.100.7, 192.0.2.1"), ip(1));
+        assert_eq!(header_ip("203.0.113.9,198.51.100.7,192.0.2.1"), ip(1));
+        assert_eq!(header_ip("192.0.2.1, unknown"), None);
+        assert_eq!(header_ip(""), None);
+    }
+}
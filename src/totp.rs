@@ -0,0 +1,220 @@
+//! Time-based one-time passwords (RFC 6238) as authenticator apps make
+//! them: HMAC-SHA1, 6 digits, 30 second steps.
+
+use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit, Nonce};
+use hmac::{Hmac, Mac};
+use qrcode::{render::svg, QrCode};
+use serde::Deserialize;
+use sha1::Sha1;
+
+const STEP: i64 = 30;
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// The `totp` table in `Rocket.toml`
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TotpConfig {
+    /// Shown in authenticator apps next to the username
+    pub issuer: String,
+    /// 64 hex digits; the key TOTP secrets are encrypted with
+    pub key: Option<String>,
+}
+
+impl Default for TotpConfig {
+    fn default() -> Self {
+        Self {
+            issuer: "Tic-tac-toe".to_owned(),
+            key: None,
+        }
+    }
+}
+
+/// Base32 without padding, the way secrets go into otpauth URIs
+pub fn base32(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &byte in bytes {
+        buffer = ((buffer << 8) | byte as u32) & 0xffff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+pub fn new_secret() -> Vec<u8> {
+    rand::random::<[u8; 20]>().to_vec()
+}
+
+/// The code of the step `counter`
+fn code_at(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(secret).expect("HMAC takes keys of any size");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+    let value = u32::from_be_bytes([
+        hash[offset],
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]) & 0x7fff_ffff;
+    value % 1_000_000
+}
+
+/// The time step `code` belongs to, if it is the one for `now` or a step off
+/// either way since clocks drift. Steps up to `last_step`, the last one
+/// accepted, don't count again.
+pub fn verify(secret: &[u8], code: &str, now: i64, last_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != 6 {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let step = now / STEP;
+    (step - 1..=step + 1).find(|&step| {
+        step >= 0
+            && !matches!(last_step, Some(last) if step <= last)
+            && code_at(secret, step as u64) == code
+    })
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// What authenticator apps scan
+pub fn otpauth_uri(issuer: &str, username: &str, secret: &[u8]) -> String {
+    let issuer = percent_encode(issuer);
+    format!(
+        "otpauth://totp/{issuer}:{}?secret={}&issuer={issuer}&algorithm=SHA1&digits=6&period={STEP}",
+        percent_encode(username),
+        base32(secret)
+    )
+}
+
+pub fn qr_svg(text: &str) -> Option<String> {
+    let code = QrCode::new(text.as_bytes()).ok()?;
+    Some(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+/// Encrypts TOTP secrets for the database, so a leaked database doesn't
+/// leak second factors too
+pub struct SecretBox(Aes256Gcm);
+
+impl SecretBox {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self(Aes256Gcm::new_from_slice(key).expect("the key has 32 bytes"))
+    }
+
+    /// `key` as 64 hex digits
+    pub fn from_hex(key: &str) -> Option<Self> {
+        if key.len() != 64 || !key.is_ascii() {
+            return None;
+        }
+        let mut bytes = [0; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&key[i * 2..i * 2 + 2], 16).ok()?;
+        }
+        Some(Self::new(&bytes))
+    }
+
+    pub fn seal(&self, plain: &[u8]) -> String {
+        let nonce: [u8; 12] = rand::random();
+        let mut sealed = nonce.to_vec();
+        sealed.extend(
+            self.0
+                .encrypt(Nonce::from_slice(&nonce), plain)
+                .expect("encryption can't fail"),
+        );
+        base64::encode(sealed)
+    }
+
+    pub fn open(&self, sealed: &str) -> Option<Vec<u8>> {
+        let sealed = base64::decode(sealed).ok()?;
+        if sealed.len() < 12 {
+            return None;
+        }
+        let (nonce, cipher) = sealed.split_at(12);
+        self.0.decrypt(Nonce::from_slice(nonce), cipher).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{base32, code_at, verify, SecretBox, STEP};
+
+    /// The SHA-1 secret of RFC 6238's test vectors
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn rfc_6238_vectors() {
+        // The RFC lists 8 digits, the last 6 are what authenticator apps show
+        for (time, code) in [
+            (59, 94287082),
+            (1111111109, 7081804),
+            (1111111111, 14050471),
+            (1234567890, 89005924),
+            (2000000000, 69279037),
+            (20000000000, 65353130),
+        ] {
+            assert_eq!(code_at(SECRET, time / STEP as u64), code % 1_000_000);
+        }
+    }
+
+    #[test]
+    fn codes_work_one_step_off_and_only_once() {
+        let now = 1111111111;
+        let step = now / STEP;
+        assert_eq!(verify(SECRET, "050471", now, None), Some(step));
+        assert_eq!(verify(SECRET, " 050471 ", now + STEP, None), Some(step));
+        assert_eq!(verify(SECRET, "050471", now + 2 * STEP, None), None);
+        assert_eq!(verify(SECRET, "050471", now, Some(step)), None);
+        assert_eq!(verify(SECRET, "050471", now, Some(step - 1)), Some(step));
+        assert_eq!(verify(SECRET, "50471", now, None), None);
+        assert_eq!(verify(SECRET, "05047a", now, None), None);
+    }
+
+    #[test]
+    fn base32_matches_rfc_4648() {
+        // RFC 4648 vectors, without the padding otpauth URIs leave out
+        for (plain, encoded) in [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ] {
+            assert_eq!(base32(plain.as_bytes()), encoded);
+        }
+        assert_eq!(base32(SECRET), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    }
+
+    #[test]
+    fn sealed_secrets_only_open_with_their_key() {
+        let key = "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff";
+        let secrets = SecretBox::from_hex(key).unwrap();
+        let sealed = secrets.seal(SECRET);
+        assert_ne!(secrets.seal(SECRET), sealed);
+        assert_eq!(secrets.open(&sealed).as_deref(), Some(SECRET));
+
+        let other = SecretBox::new(&[7; 32]);
+        assert_eq!(other.open(&sealed), None);
+        assert_eq!(secrets.open("not base64!"), None);
+        assert_eq!(secrets.open(&sealed[..8]), None);
+        assert!(SecretBox::from_hex("too short").is_none());
+    }
+}
@@ -0,0 +1,231 @@
+//! Plays hub games with an engine speaking the protocol of `tictac::engine`.
+//!
+//! ```text
+//! tictac-bridge --server http://localhost:8000 --user bot --api-key ttb_... \
+//!     [--movetime 1000] [--join] [--accept] -- ./my-engine --its-own-args
+//! ```
+//!
+//! The bridge plays as `--user`, authenticated with a bot's `--api-key` (or
+//! a regular account's `--password`). Whenever it is that account's turn in a
+//! hub game it sends the position to the engine and plays its `bestmove`.
+//! With `--join` it also takes the free seat of games waiting for an
+//! opponent, and with `--accept` it accepts every challenge sent to it.
+
+use std::{collections::HashSet, process::exit, time::Duration};
+
+use tictac::{
+    api::{Coord, HubEntry, RegisterData, TurnData},
+    engine::EngineProcess,
+    Cell, Game, GameResult, Move,
+};
+use tictac_client::{Client, Error};
+
+const POLL: Duration = Duration::from_secs(1);
+
+struct Options {
+    server: String,
+    user: String,
+    password: String,
+    api_key: Option<String>,
+    movetime: u64,
+    join: bool,
+    accept: bool,
+    engine: Vec<String>,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: tictac-bridge --user <name> (--api-key <key> | --password <password>) \
+         [--server <url>] [--movetime <ms>] [--join] [--accept] -- <engine> [args...]"
+    );
+    exit(2)
+}
+
+fn parse_options() -> Options {
+    let mut options = Options {
+        server: "http://localhost:8000".to_owned(),
+        user: String::new(),
+        password: String::new(),
+        api_key: None,
+        movetime: 1000,
+        join: false,
+        accept: false,
+        engine: Vec::new(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--server" => options.server = value(),
+            "--user" => options.user = value(),
+            "--password" => options.password = value(),
+            "--api-key" => options.api_key = Some(value()),
+            "--movetime" => options.movetime = value().parse().unwrap_or_else(|_| usage()),
+            "--join" => options.join = true,
+            "--accept" => options.accept = true,
+            "--" => {
+                options.engine = args.collect();
+                break;
+            }
+            _ => usage(),
+        }
+    }
+    if options.user.is_empty() || options.engine.is_empty() {
+        usage()
+    }
+    options
+}
+
+/// The request body for a turn made of `moves`
+fn turn_data(moves: &[Move]) -> Option<TurnData> {
+    let (first, rest) = moves.split_first()?;
+    Some(TurnData {
+        extra: rest
+            .iter()
+            .map(|mv| Coord {
+                symbol: mv.symbol,
+                number: mv.number,
+                ..Coord::at(mv.x, mv.y)
+            })
+            .collect(),
+        symbol: first.symbol,
+        number: first.number,
+        ..TurnData::at(first.x, first.y)
+    })
+}
+
+/// The seat to take in a game waiting for an opponent
+fn open_seat(entry: &HubEntry, user: &str) -> Option<Cell> {
+    if entry.game.result() != GameResult::Ongoing {
+        return None;
+    }
+    match (&entry.x_player, &entry.o_player) {
+        (Some(x), None) if x != user => Some(Cell::O),
+        (None, Some(o)) if o != user => Some(Cell::X),
+        _ => None,
+    }
+}
+
+struct Bridge {
+    options: Options,
+    client: Client,
+    engine: EngineProcess,
+    /// Games the engine has been told about with `newgame`
+    started: HashSet<u64>,
+    /// How long the server asked us to wait before polling again
+    retry_after: Option<Duration>,
+}
+
+impl Bridge {
+    /// Describes a failed request, and slows down if there were too many
+    fn failed(&mut self, e: Error) -> String {
+        if let Error::TooManyRequests(secs) = e {
+            let wait = Duration::from_secs(secs.unwrap_or(10));
+            self.retry_after = Some(self.retry_after.map_or(wait, |w| w.max(wait)));
+        }
+        e.to_string()
+    }
+
+    async fn accept_challenges(&mut self) -> Result<(), String> {
+        let challenges = self
+            .client
+            .challenges(None)
+            .await
+            .map_err(|e| self.failed(e))?;
+        for challenge in challenges {
+            if challenge.to != self.options.user {
+                continue;
+            }
+            match self.client.accept(challenge.id).await {
+                Ok(game) => eprintln!("accepted {}'s challenge, game {game}", challenge.from),
+                Err(e) => eprintln!("challenge {}: {}", challenge.id, self.failed(e)),
+            }
+        }
+        Ok(())
+    }
+
+    async fn poll(&mut self) -> Result<(), String> {
+        if self.options.accept {
+            self.accept_challenges().await?;
+        }
+        let mut games: Vec<_> = self
+            .client
+            .games()
+            .await
+            .map_err(|e| self.failed(e))?
+            .into_iter()
+            .collect();
+        games.sort_by_key(|(id, _)| *id);
+        for (id, mut entry) in games {
+            if self.options.join {
+                if let Some(side) = open_seat(&entry, &self.options.user) {
+                    entry = self
+                        .client
+                        .join(id, &RegisterData { side })
+                        .await
+                        .map_err(|e| self.failed(e))?;
+                    eprintln!("joined game {id} as {side:?}");
+                }
+            }
+            let side = entry.side_of(&self.options.user);
+            if side.is_none() || entry.game.current_player() != side {
+                continue;
+            }
+            if entry.game.result() != GameResult::Ongoing {
+                continue;
+            }
+            if self.started.insert(id) {
+                self.engine.new_game().map_err(|e| e.to_string())?;
+            }
+            let moves = self
+                .engine
+                .best_move(&entry.game, Some(self.options.movetime))
+                .map_err(|e| e.to_string())?;
+            let data = turn_data(&moves).ok_or("engine had no move")?;
+            let after = self
+                .client
+                .turn(id, &data)
+                .await
+                .map_err(|e| self.failed(e))?;
+            if after.game.history().len() == entry.game.history().len() {
+                eprintln!("game {id}: the server rejected the engine's turn");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let options = parse_options();
+    let engine =
+        EngineProcess::spawn(&options.engine[0], &options.engine[1..]).unwrap_or_else(|e| {
+            eprintln!("could not start the engine: {e}");
+            exit(1)
+        });
+    eprintln!("engine: {}", engine.name);
+    let mut client = Client::new(&options.server);
+    if let Some(key) = &options.api_key {
+        client = client.with_token(key.clone());
+    } else if let Err(e) = client.login(&options.user, &options.password).await {
+        eprintln!("could not log in: {e}");
+        exit(1)
+    }
+    let mut bridge = Bridge {
+        options,
+        client,
+        engine,
+        started: HashSet::new(),
+        retry_after: None,
+    };
+    loop {
+        if let Err(e) = bridge.poll().await {
+            eprintln!("{e}");
+        }
+        let wait = bridge
+            .retry_after
+            .take()
+            .map_or(POLL, |wait| wait.max(POLL));
+        tokio::time::sleep(wait).await;
+    }
+}
@@ -0,0 +1,865 @@
+//! Async client for the game server. Request and response bodies come from
+//! [`tictac::api`], the same types the server uses.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), tictac_client::Error> {
+//! use tictac_client::{api::ResetData, Client};
+//!
+//! let mut client = Client::new("http://localhost:8000");
+//! client.login("alice", "secret").await?;
+//! let id = client.create_game(&ResetData::classic(3, 3)).await?;
+//! let entry = client.game(id).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+pub use tictac::api;
+use tictac::{
+    api::{
+        BotCreated, BotData, Challenge, ChallengeData, DeleteAccountData, EmailData,
+        ForgotPassword, HubEntry, JwkSet, LoginResult, MailToken, ModerationAction, PasswordChange,
+        PasswordReset, RefreshData, RegisterData, RegistrationResult, RenameData, Report,
+        ReportData, ReportDecision, ReportReason, ReportStatus, ResetData, Role, RoleData,
+        SanctionData, SecondFactorData, ServerStats, SessionInfo, TokenPair, TotpCode,
+        TotpEnrollment, TurnData, UserData, UserSummary,
+    },
+    TicTacToeGame,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request never got an answer, or the answer was not what the route returns
+    Http(reqwest::Error),
+    /// No such game, or the route needs a login and there is none
+    NotFound,
+    /// Any other status the server answered with
+    Status(StatusCode),
+    /// Wrong username or password
+    BadCredentials,
+    /// The route needs a login
+    NotLoggedIn,
+    /// The password was right, but the account has 2FA: finish with
+    /// [`Client::login_second_factor`] and this ticket
+    SecondFactorRequired(String),
+    /// The server answered, but refused to do what was asked
+    Rejected(&'static str),
+    /// Too many attempts; the server asks to wait this many seconds
+    TooManyRequests(Option<u64>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "request failed: {e}"),
+            Error::NotFound => write!(f, "not found"),
+            Error::Status(status) => write!(f, "server answered {status}"),
+            Error::BadCredentials => write!(f, "wrong username or password"),
+            Error::NotLoggedIn => write!(f, "not logged in"),
+            Error::SecondFactorRequired(_) => write!(f, "a two-factor code is needed"),
+            Error::Rejected(what) => write!(f, "{what}"),
+            Error::TooManyRequests(Some(secs)) => write!(f, "too many attempts, retry in {secs}s"),
+            Error::TooManyRequests(None) => write!(f, "too many attempts"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Routes that answer `None` on refusal show up as 404
+fn rejected(e: Error, what: &'static str) -> Error {
+    match e {
+        Error::NotFound => Error::Rejected(what),
+        e => e,
+    }
+}
+
+/// What an unsuccessful status means, with the `Retry-After` header if any
+fn status_error(status: StatusCode, retry_after: Option<&str>) -> Error {
+    match status {
+        StatusCode::NOT_FOUND => Error::NotFound,
+        StatusCode::TOO_MANY_REQUESTS => {
+            Error::TooManyRequests(retry_after.and_then(|value| value.parse().ok()))
+        }
+        status => Error::Status(status),
+    }
+}
+
+/// A login token and when it runs out
+#[derive(Clone, Debug)]
+pub struct Token {
+    pub token: String,
+    /// Unix timestamp from the token's `exp` claim
+    pub expires: Option<i64>,
+}
+
+impl Token {
+    pub fn new(token: String) -> Self {
+        Self {
+            expires: expiry_of(&token),
+            token,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        matches!(self.expires, Some(exp) if exp <= now)
+    }
+}
+
+/// Reads `exp` from the token payload. The signature is the server's business.
+fn expiry_of(token: &str) -> Option<i64> {
+    #[derive(Deserialize)]
+    struct Claims {
+        exp: i64,
+    }
+    let payload = token.split('.').nth(1)?;
+    let json = base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok()?;
+    serde_json::from_slice::<Claims>(&json).ok().map(|c| c.exp)
+}
+
+pub struct Client {
+    base: String,
+    http: reqwest::Client,
+    token: Option<Token>,
+    /// Buys a new token once the current one expires
+    refresh_token: Option<String>,
+    /// Kept after [`Client::login`] to log in again when refreshing fails
+    credentials: Option<UserData>,
+}
+
+impl Client {
+    pub fn new(base: &str) -> Self {
+        Self {
+            base: base.trim_end_matches('/').to_owned(),
+            http: reqwest::Client::new(),
+            token: None,
+            refresh_token: None,
+            credentials: None,
+        }
+    }
+
+    /// Uses an existing token instead of logging in
+    pub fn with_token(mut self, token: String) -> Self {
+        self.token = Some(Token::new(token));
+        self
+    }
+
+    pub fn token(&self) -> Option<&Token> {
+        self.token.as_ref()
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base)
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(&token.token),
+            None => request,
+        }
+    }
+
+    fn keep(&mut self, tokens: TokenPair) {
+        self.token = Some(Token::new(tokens.access_token));
+        self.refresh_token = Some(tokens.refresh_token);
+    }
+
+    /// A valid token for routes that need a login, refreshing it or logging
+    /// in again if the old one ran out
+    async fn ensure_token(&mut self) -> Result<()> {
+        match &self.token {
+            Some(token) if !token.is_expired() => return Ok(()),
+            _ => {}
+        }
+        if self.refresh_token.is_some() && self.refresh().await.is_ok() {
+            return Ok(());
+        }
+        match self.credentials.clone() {
+            Some(data) => self.login(&data.username, &data.password).await,
+            None => Err(Error::NotLoggedIn),
+        }
+    }
+
+    /// [`Client::ensure_token`] for routes that work without a login, but
+    /// show players more of their own games with one
+    async fn ensure_token_if_logged_in(&mut self) -> Result<()> {
+        if self.token.is_none() && self.credentials.is_none() {
+            return Ok(());
+        }
+        self.ensure_token().await
+    }
+
+    async fn send(request: RequestBuilder) -> Result<Response> {
+        let response = request.send().await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok());
+        Err(status_error(response.status(), retry_after))
+    }
+
+    async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+        Ok(Self::send(request).await?.json().await?)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Self::json(self.authorized(self.http.get(self.url(path)))).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        Self::json(self.authorized(self.http.post(self.url(path))).json(body)).await
+    }
+
+    /// `POST /user/register`
+    pub async fn register(&self, username: &str, password: &str) -> Result<RegistrationResult> {
+        let data = UserData {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        };
+        self.post("/user/register", &data)
+            .await
+            .map_err(|e| rejected(e, "username is taken"))
+    }
+
+    /// `POST /user/login`, keeping the tokens for later requests
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        let data = UserData {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        };
+        let result: Option<LoginResult> = self.post("/user/login", &data).await?;
+        match result.ok_or(Error::BadCredentials)? {
+            LoginResult::LoggedIn(tokens) => {
+                self.keep(tokens);
+                self.credentials = Some(data);
+                Ok(())
+            }
+            LoginResult::SecondFactor { ticket } => Err(Error::SecondFactorRequired(ticket)),
+        }
+    }
+
+    /// `POST /user/login/second-factor` with the ticket from
+    /// [`Error::SecondFactorRequired`] and a TOTP or recovery code
+    pub async fn login_second_factor(&mut self, ticket: &str, code: &str) -> Result<()> {
+        let data = SecondFactorData {
+            ticket: ticket.to_owned(),
+            code: code.to_owned(),
+        };
+        let tokens: Option<TokenPair> = self.post("/user/login/second-factor", &data).await?;
+        self.keep(tokens.ok_or(Error::Rejected("wrong code or expired ticket"))?);
+        Ok(())
+    }
+
+    /// `POST /user/2fa/enroll`: a new TOTP secret, waiting for
+    /// [`Client::enable_totp`]
+    pub async fn enroll_totp(&mut self) -> Result<TotpEnrollment> {
+        self.ensure_token().await?;
+        self.post("/user/2fa/enroll", &())
+            .await
+            .map_err(|e| rejected(e, "2FA is already on"))
+    }
+
+    /// `POST /user/2fa/enable`, returning the recovery codes
+    pub async fn enable_totp(&mut self, code: &str) -> Result<Vec<String>> {
+        self.ensure_token().await?;
+        let data = TotpCode {
+            code: code.to_owned(),
+        };
+        self.post("/user/2fa/enable", &data)
+            .await
+            .map_err(|e| rejected(e, "wrong code"))
+    }
+
+    /// `POST /user/2fa/disable` with a TOTP or recovery code
+    pub async fn disable_totp(&mut self, code: &str) -> Result<()> {
+        self.ensure_token().await?;
+        let data = TotpCode {
+            code: code.to_owned(),
+        };
+        self.post::<_, bool>("/user/2fa/disable", &data)
+            .await
+            .map(|_| ())
+            .map_err(|e| rejected(e, "wrong code"))
+    }
+
+    /// `POST /user/2fa/recovery-codes`: new recovery codes for the old ones
+    pub async fn new_recovery_codes(&mut self, code: &str) -> Result<Vec<String>> {
+        self.ensure_token().await?;
+        let data = TotpCode {
+            code: code.to_owned(),
+        };
+        self.post("/user/2fa/recovery-codes", &data)
+            .await
+            .map_err(|e| rejected(e, "wrong code"))
+    }
+
+    /// `POST /user/refresh`: a new token without logging in again
+    pub async fn refresh(&mut self) -> Result<()> {
+        let data = RefreshData {
+            refresh_token: self.refresh_token.clone().ok_or(Error::NotLoggedIn)?,
+        };
+        let tokens: Option<TokenPair> = self.post("/user/refresh", &data).await?;
+        self.keep(tokens.ok_or(Error::NotLoggedIn)?);
+        Ok(())
+    }
+
+    /// `POST /user/logout`: ends this session, or with `all` every session
+    /// of the user, and forgets the tokens and the credentials
+    pub async fn logout(&mut self, all: bool) -> Result<()> {
+        let ended = match self.ensure_token().await {
+            Ok(()) => {
+                let request = self.http.post(self.url("/user/logout"));
+                let request = self.authorized(request).query(&[("all", all)]);
+                Self::json::<bool>(request).await.map(|_| ())
+            }
+            Err(e) => Err(e),
+        };
+        self.token = None;
+        self.refresh_token = None;
+        self.credentials = None;
+        ended
+    }
+
+    /// `GET /user/sessions`
+    pub async fn sessions(&mut self) -> Result<Vec<SessionInfo>> {
+        self.ensure_token().await?;
+        self.get("/user/sessions").await
+    }
+
+    /// `DELETE /user/sessions/<id>`, e.g. for a lost device
+    pub async fn end_session(&mut self, id: i32) -> Result<()> {
+        self.ensure_token().await?;
+        let request = self.http.delete(self.url(&format!("/user/sessions/{id}")));
+        let ended: bool = Self::json(self.authorized(request)).await?;
+        if ended {
+            Ok(())
+        } else {
+            Err(Error::Rejected("no such session"))
+        }
+    }
+
+    /// `POST /user/password`. The server ends every session and starts a new
+    /// one for us.
+    pub async fn change_password(&mut self, old: &str, new: &str) -> Result<()> {
+        self.ensure_token().await?;
+        let data = PasswordChange {
+            old_password: old.to_owned(),
+            new_password: new.to_owned(),
+        };
+        let tokens: TokenPair = self
+            .post("/user/password", &data)
+            .await
+            .map_err(|e| rejected(e, "wrong password"))?;
+        self.keep(tokens);
+        if let Some(credentials) = &mut self.credentials {
+            credentials.password = new.to_owned();
+        }
+        Ok(())
+    }
+
+    /// `POST /user/rename`
+    pub async fn rename(&mut self, username: &str) -> Result<()> {
+        self.ensure_token().await?;
+        let data = RenameData {
+            username: username.to_owned(),
+        };
+        let tokens: TokenPair = self
+            .post("/user/rename", &data)
+            .await
+            .map_err(|e| rejected(e, "username is taken"))?;
+        self.keep(tokens);
+        if let Some(credentials) = &mut self.credentials {
+            credentials.username = username.to_owned();
+        }
+        Ok(())
+    }
+
+    /// `DELETE /user`: deletes the account and our bots, then forgets the
+    /// tokens and the credentials
+    pub async fn delete_account(&mut self, password: &str) -> Result<()> {
+        self.ensure_token().await?;
+        let data = DeleteAccountData {
+            password: password.to_owned(),
+        };
+        let request = self.authorized(self.http.delete(self.url("/user")));
+        Self::json::<bool>(request.json(&data))
+            .await
+            .map_err(|e| rejected(e, "wrong password"))?;
+        self.token = None;
+        self.refresh_token = None;
+        self.credentials = None;
+        Ok(())
+    }
+
+    /// `POST /user/email`: sets our address and has a verification link
+    /// mailed to it
+    pub async fn set_email(&mut self, email: &str) -> Result<()> {
+        self.ensure_token().await?;
+        let data = EmailData {
+            email: email.to_owned(),
+        };
+        let sent: bool = self
+            .post("/user/email", &data)
+            .await
+            .map_err(|e| rejected(e, "invalid email address"))?;
+        if sent {
+            Ok(())
+        } else {
+            Err(Error::Rejected("the verification mail could not be sent"))
+        }
+    }
+
+    /// `POST /user/email/verify` with the token from the verification mail
+    pub async fn verify_email(&self, token: &str) -> Result<()> {
+        let data = MailToken {
+            token: token.to_owned(),
+        };
+        let verified: bool = self.post("/user/email/verify", &data).await?;
+        if verified {
+            Ok(())
+        } else {
+            Err(Error::Rejected("invalid or expired token"))
+        }
+    }
+
+    /// `POST /user/password/forgot`: a reset mail for the account with this
+    /// username or verified address, if there is one
+    pub async fn forgot_password(&self, login: &str) -> Result<()> {
+        let data = ForgotPassword {
+            login: login.to_owned(),
+        };
+        self.post::<_, bool>("/user/password/forgot", &data)
+            .await
+            .map(|_| ())
+    }
+
+    /// `POST /user/password/reset` with the token from the reset mail
+    pub async fn reset_password(&self, token: &str, new_password: &str) -> Result<()> {
+        let data = PasswordReset {
+            token: token.to_owned(),
+            new_password: new_password.to_owned(),
+        };
+        self.post::<_, bool>("/user/password/reset", &data)
+            .await
+            .map(|_| ())
+            .map_err(|e| rejected(e, "invalid or expired token"))
+    }
+
+    /// `POST /user/check`: who the server thinks we are
+    pub async fn check_logged_in(&self) -> Result<Option<String>> {
+        Self::json(self.authorized(self.http.post(self.url("/user/check")))).await
+    }
+
+    /// `GET /.well-known/jwks.json`: the public keys that verify access
+    /// tokens, for services that check them without asking the server
+    pub async fn jwks(&self) -> Result<JwkSet> {
+        self.get("/.well-known/jwks.json").await
+    }
+
+    /// `GET /`: the single homepage game
+    pub async fn homepage(&self) -> Result<TicTacToeGame> {
+        self.get("/").await
+    }
+
+    /// `POST /turn` on the homepage game
+    pub async fn homepage_turn(&self, data: &TurnData) -> Result<TicTacToeGame> {
+        self.post("/turn", data).await
+    }
+
+    /// `POST /reset` on the homepage game
+    pub async fn homepage_reset(&self, data: &ResetData) -> Result<TicTacToeGame> {
+        self.post("/reset", data).await
+    }
+
+    /// `GET /games`
+    pub async fn games(&mut self) -> Result<HashMap<u64, HubEntry>> {
+        self.ensure_token_if_logged_in().await?;
+        self.get("/games").await
+    }
+
+    /// `POST /games`
+    pub async fn create_game(&mut self, data: &ResetData) -> Result<u64> {
+        self.ensure_token().await?;
+        let id: Option<u64> = self.post("/games", data).await?;
+        id.ok_or(Error::Rejected("invalid game rules"))
+    }
+
+    /// `GET /games/<id>`
+    pub async fn game(&mut self, id: u64) -> Result<HubEntry> {
+        self.ensure_token_if_logged_in().await?;
+        self.get(&format!("/games/{id}")).await
+    }
+
+    /// `POST /games/<id>/register`: takes a seat, if it is free
+    pub async fn join(&mut self, id: u64, data: &RegisterData) -> Result<HubEntry> {
+        self.ensure_token().await?;
+        self.post(&format!("/games/{id}/register"), data).await
+    }
+
+    /// `POST /games/<id>/turn`. The server answers with the game either way,
+    /// so compare it to see whether the turn was accepted.
+    pub async fn turn(&mut self, id: u64, data: &TurnData) -> Result<HubEntry> {
+        self.ensure_token().await?;
+        self.post(&format!("/games/{id}/turn"), data).await
+    }
+
+    /// `POST /games/<id>/reset`
+    pub async fn reset(&mut self, id: u64, data: &ResetData) -> Result<HubEntry> {
+        self.ensure_token().await?;
+        self.post(&format!("/games/{id}/reset"), data).await
+    }
+
+    /// `DELETE /games/<id>`
+    pub async fn delete(&mut self, id: u64) -> Result<()> {
+        self.ensure_token().await?;
+        let request = self.authorized(self.http.delete(self.url(&format!("/games/{id}"))));
+        let deleted: bool = Self::json(request).await?;
+        if deleted {
+            Ok(())
+        } else {
+            Err(Error::Rejected("only players can delete a drawn game"))
+        }
+    }
+
+    /// `GET /games/<id>/export`: the game record, or just the position
+    pub async fn export(&mut self, id: u64, position: bool) -> Result<String> {
+        self.ensure_token_if_logged_in().await?;
+        let request = self.http.get(self.url(&format!("/games/{id}/export")));
+        let request = self.authorized(request).query(&[("position", position)]);
+        Ok(Self::send(request).await?.text().await?)
+    }
+
+    /// `POST /games/import` with a game record or position
+    pub async fn import(&mut self, notation: &str) -> Result<u64> {
+        self.ensure_token().await?;
+        let request = self.authorized(self.http.post(self.url("/games/import")));
+        let id: Option<u64> = Self::json(request.body(notation.to_owned())).await?;
+        id.ok_or(Error::Rejected("invalid game notation"))
+    }
+
+    /// `GET /games/<id>/board.svg`
+    pub async fn board_svg(&mut self, id: u64) -> Result<String> {
+        self.ensure_token_if_logged_in().await?;
+        let request = self.http.get(self.url(&format!("/games/{id}/board.svg")));
+        Ok(Self::send(self.authorized(request)).await?.text().await?)
+    }
+
+    /// `GET /games/<id>/board.png`
+    pub async fn board_png(&mut self, id: u64) -> Result<Vec<u8>> {
+        self.ensure_token_if_logged_in().await?;
+        let request = self.http.get(self.url(&format!("/games/{id}/board.png")));
+        Ok(Self::send(self.authorized(request))
+            .await?
+            .bytes()
+            .await?
+            .to_vec())
+    }
+
+    /// `GET /games/<id>/replay.gif`, `delay` milliseconds per move
+    pub async fn replay_gif(&mut self, id: u64, delay: Option<u32>) -> Result<Vec<u8>> {
+        self.ensure_token_if_logged_in().await?;
+        let mut request =
+            self.authorized(self.http.get(self.url(&format!("/games/{id}/replay.gif"))));
+        if let Some(delay) = delay {
+            request = request.query(&[("delay", delay)]);
+        }
+        Ok(Self::send(request).await?.bytes().await?.to_vec())
+    }
+
+    /// `POST /bots`: a bot account owned by the logged in user
+    pub async fn create_bot(&mut self, username: &str) -> Result<BotCreated> {
+        self.ensure_token().await?;
+        let data = BotData {
+            username: username.to_owned(),
+        };
+        self.post("/bots", &data)
+            .await
+            .map_err(|e| rejected(e, "username is taken"))
+    }
+
+    /// `POST /bots/<username>/keys`: another API key for one of our bots
+    pub async fn create_bot_key(&mut self, username: &str) -> Result<String> {
+        self.ensure_token().await?;
+        self.post(&format!("/bots/{username}/keys"), &()).await
+    }
+
+    /// `POST /challenges`
+    pub async fn challenge(&mut self, data: &ChallengeData) -> Result<u64> {
+        self.ensure_token().await?;
+        self.post("/challenges", data)
+            .await
+            .map_err(|e| rejected(e, "invalid challenge"))
+    }
+
+    /// `GET /challenges`, waiting up to `wait` seconds for one addressed to us
+    pub async fn challenges(&mut self, wait: Option<u64>) -> Result<Vec<Challenge>> {
+        self.ensure_token().await?;
+        let mut request = self.authorized(self.http.get(self.url("/challenges")));
+        if let Some(wait) = wait {
+            request = request.query(&[("wait", wait)]);
+        }
+        Self::json(request).await
+    }
+
+    /// `POST /challenges/<id>/accept`, returning the id of the new game
+    pub async fn accept(&mut self, id: u64) -> Result<u64> {
+        self.ensure_token().await?;
+        let request = self
+            .http
+            .post(self.url(&format!("/challenges/{id}/accept")));
+        Self::json(self.authorized(request))
+            .await
+            .map_err(|e| rejected(e, "the challenge can't be accepted"))
+    }
+
+    /// `POST /challenges/<id>/decline`
+    pub async fn decline(&mut self, id: u64) -> Result<()> {
+        self.ensure_token().await?;
+        let request = self
+            .http
+            .post(self.url(&format!("/challenges/{id}/decline")));
+        let declined: bool = Self::json(self.authorized(request)).await?;
+        if declined {
+            Ok(())
+        } else {
+            Err(Error::Rejected("not our challenge"))
+        }
+    }
+
+    /// `GET /admin/users`, for moderators
+    pub async fn users(
+        &mut self,
+        offset: u64,
+        limit: u64,
+        banned: bool,
+    ) -> Result<Vec<UserSummary>> {
+        self.ensure_token().await?;
+        let request = self.authorized(self.http.get(self.url("/admin/users")));
+        let request = request.query(&[("offset", offset), ("limit", limit)]);
+        Self::json(request.query(&[("banned", banned)])).await
+    }
+
+    /// `POST /admin/users/<id>/ban`, for `days` or until lifted
+    pub async fn ban(&mut self, id: i32, data: &SanctionData) -> Result<UserSummary> {
+        self.ensure_token().await?;
+        self.post(&format!("/admin/users/{id}/ban"), data)
+            .await
+            .map_err(|e| rejected(e, "no such account below our role"))
+    }
+
+    /// `DELETE /admin/users/<id>/ban`
+    pub async fn unban(&mut self, id: i32) -> Result<UserSummary> {
+        self.ensure_token().await?;
+        let request = self
+            .http
+            .delete(self.url(&format!("/admin/users/{id}/ban")));
+        Self::json(self.authorized(request))
+            .await
+            .map_err(|e| rejected(e, "no such account below our role"))
+    }
+
+    /// `POST /admin/users/<id>/mute`, for `days` or until lifted
+    pub async fn mute(&mut self, id: i32, data: &SanctionData) -> Result<UserSummary> {
+        self.ensure_token().await?;
+        self.post(&format!("/admin/users/{id}/mute"), data)
+            .await
+            .map_err(|e| rejected(e, "no such account below our role"))
+    }
+
+    /// `DELETE /admin/users/<id>/mute`
+    pub async fn unmute(&mut self, id: i32) -> Result<UserSummary> {
+        self.ensure_token().await?;
+        let request = self
+            .http
+            .delete(self.url(&format!("/admin/users/{id}/mute")));
+        Self::json(self.authorized(request))
+            .await
+            .map_err(|e| rejected(e, "no such account below our role"))
+    }
+
+    /// `POST /admin/users/<id>/role`, for admins
+    pub async fn set_role(&mut self, id: i32, role: Role) -> Result<UserSummary> {
+        self.ensure_token().await?;
+        self.post(&format!("/admin/users/{id}/role"), &RoleData { role })
+            .await
+            .map_err(|e| rejected(e, "bots and our own account have no say in roles"))
+    }
+
+    /// `POST /admin/games/<id>/abort`
+    pub async fn abort_game(&mut self, id: u64) -> Result<HubEntry> {
+        self.ensure_token().await?;
+        self.post(&format!("/admin/games/{id}/abort"), &()).await
+    }
+
+    /// `DELETE /admin/games/<id>`: any game, unlike [`Client::delete`]
+    pub async fn delete_any_game(&mut self, id: u64) -> Result<()> {
+        self.ensure_token().await?;
+        let request = self.http.delete(self.url(&format!("/admin/games/{id}")));
+        let deleted: bool = Self::json(self.authorized(request)).await?;
+        if deleted {
+            Ok(())
+        } else {
+            Err(Error::NotFound)
+        }
+    }
+
+    /// `GET /admin/stats`
+    pub async fn server_stats(&mut self) -> Result<ServerStats> {
+        self.ensure_token().await?;
+        self.get("/admin/stats").await
+    }
+
+    /// `GET /admin/log`, newest first
+    pub async fn moderation_log(
+        &mut self,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<ModerationAction>> {
+        self.ensure_token().await?;
+        let request = self.authorized(self.http.get(self.url("/admin/log")));
+        Self::json(request.query(&[("offset", offset), ("limit", limit)])).await
+    }
+
+    /// `POST /games/<id>/report`: reports our opponent in the game, returning
+    /// the report's id
+    pub async fn report(
+        &mut self,
+        id: u64,
+        reason: ReportReason,
+        comment: Option<&str>,
+    ) -> Result<i32> {
+        self.ensure_token().await?;
+        let data = ReportData {
+            reason,
+            comment: comment.map(str::to_owned),
+        };
+        self.post(&format!("/games/{id}/report"), &data)
+            .await
+            .map_err(|e| rejected(e, "not our game, already reported, or muted"))
+    }
+
+    /// `GET /moderation/reports`, the moderation queue
+    pub async fn reports(
+        &mut self,
+        status: ReportStatus,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Report>> {
+        self.ensure_token().await?;
+        let request = self.authorized(self.http.get(self.url("/moderation/reports")));
+        let request = request.query(&[("status", status.as_str())]);
+        Self::json(request.query(&[("offset", offset), ("limit", limit)])).await
+    }
+
+    /// `POST /moderation/reports/<id>`: closes a report as resolved or
+    /// dismissed
+    pub async fn decide_report(
+        &mut self,
+        id: i32,
+        status: ReportStatus,
+        note: Option<&str>,
+    ) -> Result<()> {
+        self.ensure_token().await?;
+        let data = ReportDecision {
+            status,
+            note: note.map(str::to_owned),
+        };
+        let closed: bool = self
+            .post(&format!("/moderation/reports/{id}"), &data)
+            .await?;
+        if closed {
+            Ok(())
+        } else {
+            Err(Error::Rejected("the report is already closed"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use reqwest::StatusCode;
+
+    use super::{expiry_of, rejected, status_error, Error};
+
+    fn token(payload: &str) -> String {
+        let payload = base64::encode_config(payload, base64::URL_SAFE_NO_PAD);
+        format!("eyJhbGciOiJFZERTQSJ9.{payload}.c2lnbmF0dXJl")
+    }
+
+    #[test]
+    fn expiry_comes_from_the_payload() {
+        assert_eq!(
+            expiry_of(&token(r#"{"sub":"alice","exp":1700000000}"#)),
+            Some(1700000000)
+        );
+        assert_eq!(expiry_of(&token(r#"{"sub":"alice"}"#)), None);
+        assert_eq!(expiry_of(&token("not json")), None);
+        assert_eq!(expiry_of("no-dots"), None);
+        assert_eq!(expiry_of("a.!!!.c"), None);
+    }
+
+    #[test]
+    fn statuses_map_to_errors() {
+        assert!(matches!(
+            status_error(StatusCode::NOT_FOUND, None),
+            Error::NotFound
+        ));
+        assert!(matches!(
+            status_error(StatusCode::TOO_MANY_REQUESTS, Some("30")),
+            Error::TooManyRequests(Some(30))
+        ));
+        assert!(matches!(
+            status_error(StatusCode::TOO_MANY_REQUESTS, Some("soon")),
+            Error::TooManyRequests(None)
+        ));
+        assert!(matches!(
+            status_error(StatusCode::INTERNAL_SERVER_ERROR, None),
+            Error::Status(StatusCode::INTERNAL_SERVER_ERROR)
+        ));
+    }
+
+    #[test]
+    fn only_not_found_counts_as_rejected() {
+        assert!(matches!(
+            rejected(Error::NotFound, "username is taken"),
+            Error::Rejected("username is taken")
+        ));
+        assert!(matches!(
+            rejected(Error::Status(StatusCode::BAD_REQUEST), "username is taken"),
+            Error::Status(StatusCode::BAD_REQUEST)
+        ));
+        assert!(matches!(
+            rejected(Error::TooManyRequests(None), "wrong code"),
+            Error::TooManyRequests(None)
+        ));
+    }
+}
@@ -0,0 +1,317 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use tictac::{
+    api::{Coord, HubEntry, RegisterData, ResetData, TurnData},
+    AnyGame, Cell, Game, GameResult,
+};
+use tictac_client::{Client, Error};
+use tokio::runtime::Runtime;
+
+pub enum Screen {
+    Login {
+        username: String,
+        password: String,
+        /// Whether the password field has focus
+        on_password: bool,
+        /// Set once the password was right but the account has 2FA
+        ticket: Option<String>,
+        code: String,
+    },
+    Lobby {
+        games: Vec<(u64, HubEntry)>,
+        selected: usize,
+    },
+    Board {
+        id: u64,
+        entry: Box<HubEntry>,
+        cursor: (usize, usize),
+        /// Stones picked so far in a multi-stone turn
+        pending: Vec<(usize, usize)>,
+        /// Symbol for Order and Chaos, number for numerical games
+        symbol: Cell,
+        number: usize,
+    },
+}
+
+pub struct App {
+    pub client: Client,
+    /// The client is async, the terminal loop is not
+    runtime: Runtime,
+    pub username: Option<String>,
+    /// Set when the server says we're polling too often
+    paused_until: Option<Instant>,
+    pub screen: Screen,
+    pub status: String,
+    pub quit: bool,
+}
+
+impl App {
+    pub fn new(client: Client) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            client,
+            runtime,
+            username: None,
+            paused_until: None,
+            screen: Screen::Login {
+                username: String::new(),
+                password: String::new(),
+                on_password: false,
+                ticket: None,
+                code: String::new(),
+            },
+            status: "Log in, Tab switches fields".to_owned(),
+            quit: false,
+        })
+    }
+
+    /// Reloads what is on screen. The server has no push channel, so this
+    /// runs on a timer.
+    pub fn refresh(&mut self) {
+        if matches!(self.paused_until, Some(until) if Instant::now() < until) {
+            return;
+        }
+        let result = match &mut self.screen {
+            Screen::Login { .. } => Ok(()),
+            Screen::Lobby { games, selected } => {
+                self.runtime.block_on(self.client.games()).map(|list| {
+                    let mut list: Vec<_> = list.into_iter().collect();
+                    list.sort_by_key(|(id, _)| *id);
+                    *selected = (*selected).min(list.len().saturating_sub(1));
+                    *games = list;
+                })
+            }
+            Screen::Board { id, entry, .. } => self
+                .runtime
+                .block_on(self.client.game(*id))
+                .map(|e| **entry = e),
+        };
+        if let Err(e) = result {
+            if let Error::TooManyRequests(secs) = e {
+                let wait = Duration::from_secs(secs.unwrap_or(10));
+                self.paused_until = Some(Instant::now() + wait);
+            }
+            self.status = e.to_string();
+        }
+    }
+
+    fn open_lobby(&mut self) {
+        self.screen = Screen::Lobby {
+            games: Vec::new(),
+            selected: 0,
+        };
+        self.status = "Enter: watch  x/o: join  n: new game  r: refresh  q: quit".to_owned();
+        self.refresh();
+    }
+
+    fn open_board(&mut self, id: u64, entry: HubEntry) {
+        let me = self.username.as_deref().unwrap_or_default();
+        let symbol = entry.side_of(me).unwrap_or(Cell::X);
+        self.screen = Screen::Board {
+            id,
+            entry: Box::new(entry),
+            cursor: (0, 0),
+            pending: Vec::new(),
+            symbol,
+            number: 0,
+        };
+        self.status = "Arrows: move  Enter: play  Space: add stone  Tab: symbol/number  Esc: lobby"
+            .to_owned();
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) {
+        match &mut self.screen {
+            Screen::Login {
+                username,
+                password,
+                on_password,
+                ticket,
+                code,
+            } => {
+                let field = match (ticket.is_some(), *on_password) {
+                    (true, _) => code,
+                    (false, true) => password,
+                    (false, false) => username,
+                };
+                match key.code {
+                    KeyCode::Esc if ticket.is_some() => {
+                        // The field is the code here
+                        *ticket = None;
+                        field.clear();
+                    }
+                    KeyCode::Esc => self.quit = true,
+                    KeyCode::Tab => *on_password = !*on_password,
+                    KeyCode::Backspace => {
+                        field.pop();
+                    }
+                    KeyCode::Char(c) => field.push(c),
+                    KeyCode::Enter => {
+                        let (username, password, ticket, code) = match &self.screen {
+                            Screen::Login {
+                                username,
+                                password,
+                                ticket,
+                                code,
+                                ..
+                            } => (
+                                username.clone(),
+                                password.clone(),
+                                ticket.clone(),
+                                code.clone(),
+                            ),
+                            _ => unreachable!(),
+                        };
+                        let result = match &ticket {
+                            Some(ticket) => self
+                                .runtime
+                                .block_on(self.client.login_second_factor(ticket, &code)),
+                            None => self
+                                .runtime
+                                .block_on(self.client.login(&username, &password)),
+                        };
+                        match result {
+                            Ok(()) => {
+                                self.username = Some(username);
+                                self.open_lobby();
+                            }
+                            Err(Error::SecondFactorRequired(new_ticket)) => {
+                                if let Screen::Login { ticket, .. } = &mut self.screen {
+                                    *ticket = Some(new_ticket);
+                                }
+                                self.status =
+                                    "Enter a code from your authenticator app, Esc goes back"
+                                        .to_owned();
+                            }
+                            Err(e) => self.status = e.to_string(),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Screen::Lobby { games, selected } => {
+                let current = games.get(*selected).cloned();
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                    KeyCode::Up | KeyCode::Char('k') => *selected = selected.saturating_sub(1),
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        *selected = (*selected + 1).min(games.len().saturating_sub(1))
+                    }
+                    KeyCode::Char('r') => self.refresh(),
+                    KeyCode::Char('n') => {
+                        let data = ResetData::classic(3, 3);
+                        match self.runtime.block_on(self.client.create_game(&data)) {
+                            Ok(id) => self.status = format!("Created game {id}"),
+                            Err(e) => self.status = e.to_string(),
+                        }
+                        self.refresh();
+                    }
+                    KeyCode::Enter => {
+                        if let Some((id, entry)) = current {
+                            self.open_board(id, entry);
+                        }
+                    }
+                    KeyCode::Char(c @ ('x' | 'o')) => {
+                        if let Some((id, _)) = current {
+                            let side = if c == 'x' { Cell::X } else { Cell::O };
+                            let data = RegisterData { side };
+                            match self.runtime.block_on(self.client.join(id, &data)) {
+                                Ok(entry) => self.open_board(id, entry),
+                                Err(e) => self.status = e.to_string(),
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Screen::Board {
+                id,
+                entry,
+                cursor,
+                pending,
+                symbol,
+                number,
+            } => {
+                let size = entry.game.size();
+                match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => self.open_lobby(),
+                    KeyCode::Up => cursor.0 = cursor.0.saturating_sub(1),
+                    KeyCode::Down => cursor.0 = (cursor.0 + 1).min(size - 1),
+                    KeyCode::Left => cursor.1 = cursor.1.saturating_sub(1),
+                    KeyCode::Right => cursor.1 = (cursor.1 + 1).min(size - 1),
+                    KeyCode::Tab => {
+                        *symbol = symbol.flip();
+                        *number += 1;
+                    }
+                    KeyCode::Char(' ') => {
+                        if let Some(i) = pending.iter().position(|&c| c == *cursor) {
+                            pending.remove(i);
+                        } else {
+                            pending.push(*cursor);
+                        }
+                    }
+                    KeyCode::Enter => {
+                        if pending.is_empty() {
+                            pending.push(*cursor);
+                        }
+                        let data = turn_data(&entry.game, pending, *symbol, *number);
+                        pending.clear();
+                        match self.runtime.block_on(self.client.turn(*id, &data)) {
+                            Ok(e) => **entry = e,
+                            Err(e) => self.status = e.to_string(),
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Number picked with Tab among the ones the current player still has
+pub fn chosen_number(game: &AnyGame, number: usize) -> Option<u32> {
+    match game {
+        AnyGame::Numerical(game) => {
+            let numbers = game.numbers_of(game.current_player?);
+            numbers.get(number % numbers.len().max(1)).copied()
+        }
+        AnyGame::Classic(_) => None,
+    }
+}
+
+fn turn_data(game: &AnyGame, stones: &[(usize, usize)], symbol: Cell, number: usize) -> TurnData {
+    let (x, y) = stones[0];
+    TurnData {
+        extra: stones[1..].iter().map(|&(x, y)| Coord::at(x, y)).collect(),
+        symbol: match game {
+            AnyGame::Classic(game) if game.order_and_chaos => Some(symbol),
+            _ => None,
+        },
+        number: chosen_number(game, number),
+        ..TurnData::at(x, y)
+    }
+}
+
+pub fn result_text(entry: &HubEntry) -> String {
+    let name = |side: Cell| match entry.get_player(side) {
+        Some(player) if entry.is_bot(side) => format!("{player} [bot]"),
+        Some(player) => player,
+        None => "(open)".to_owned(),
+    };
+    if entry.aborted {
+        return "Aborted by a moderator".to_owned();
+    }
+    match entry.game.result() {
+        GameResult::Ongoing => match entry.game.current_player() {
+            Some(side) => format!("{side:?} to move ({})", name(side)),
+            None => "Waiting".to_owned(),
+        },
+        GameResult::Won(side) => format!("{side:?} won ({})", name(side)),
+        GameResult::Draw => "Draw".to_owned(),
+    }
+}
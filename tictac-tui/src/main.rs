@@ -0,0 +1,59 @@
+//! Terminal client for the game hub: `tictac-tui [server url]`
+
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    event::{self, Event, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use tui::{backend::CrosstermBackend, Terminal};
+
+use app::App;
+use tictac_client::Client;
+
+mod app;
+mod ui;
+
+const REFRESH: Duration = Duration::from_secs(1);
+
+fn main() -> io::Result<()> {
+    let server = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "http://localhost:8000".to_owned());
+    let mut app = App::new(Client::new(&server))?;
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    let result = run(&mut terminal, &mut app);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn run<B: tui::backend::Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    let mut last_refresh = Instant::now();
+    while !app.quit {
+        terminal.draw(|f| ui::draw(f, app))?;
+        let timeout = REFRESH.saturating_sub(last_refresh.elapsed());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.on_key(key);
+                }
+            }
+        }
+        if last_refresh.elapsed() >= REFRESH {
+            app.refresh();
+            last_refresh = Instant::now();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use tictac::{AnyGame, Cell, Game};
+use tui::{
+    backend::Backend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::app::{chosen_number, result_text, App, Screen};
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(f.size());
+    let status = Paragraph::new(app.status.as_str())
+        .block(Block::default().borders(Borders::ALL).title("Status"));
+    f.render_widget(status, chunks[1]);
+
+    match &app.screen {
+        Screen::Login {
+            username,
+            password,
+            on_password,
+            ticket,
+            code,
+        } => {
+            let focus = |focused: bool| {
+                if focused {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                }
+            };
+            let mut text = vec![
+                Spans::from(Span::styled(
+                    format!("Username: {username}"),
+                    focus(ticket.is_none() && !on_password),
+                )),
+                Spans::from(Span::styled(
+                    format!("Password: {}", "*".repeat(password.chars().count())),
+                    focus(ticket.is_none() && *on_password),
+                )),
+            ];
+            if ticket.is_some() {
+                text.push(Spans::from(Span::styled(
+                    format!("Code:     {code}"),
+                    focus(true),
+                )));
+            }
+            let login =
+                Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Login"));
+            f.render_widget(login, chunks[0]);
+        }
+        Screen::Lobby { games, selected } => {
+            let items: Vec<_> = games
+                .iter()
+                .map(|(id, entry)| {
+                    let kind = match &entry.game {
+                        AnyGame::Classic(game) => {
+                            format!("{0}x{0}, {1} in a row", game.size, game.criteria)
+                        }
+                        AnyGame::Numerical(game) => format!("{0}x{0} numerical", game.size),
+                    };
+                    ListItem::new(format!("#{id:<4} {kind:<24} {}", result_text(entry)))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title("Games"))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default();
+            state.select((!games.is_empty()).then_some(*selected));
+            f.render_stateful_widget(list, chunks[0], &mut state);
+        }
+        Screen::Board {
+            id,
+            entry,
+            cursor,
+            pending,
+            symbol,
+            number,
+        } => {
+            let size = entry.game.size();
+            let line = entry.game.winning_line().unwrap_or_default();
+            let mut rows = vec![Spans::from(result_text(entry)), Spans::from("")];
+            for x in 0..size {
+                let cells: Vec<_> = (0..size)
+                    .map(|y| {
+                        let (text, color) = match &entry.game {
+                            AnyGame::Classic(game) => match game.field[x][y] {
+                                Some(Cell::X) => (" X ".to_owned(), Color::Red),
+                                Some(Cell::O) => (" O ".to_owned(), Color::Blue),
+                                None => (" . ".to_owned(), Color::DarkGray),
+                            },
+                            AnyGame::Numerical(game) => match game.field[x][y] {
+                                Some(n) if n % 2 == 1 => (format!("{n:^3}"), Color::Red),
+                                Some(n) => (format!("{n:^3}"), Color::Blue),
+                                None => (" . ".to_owned(), Color::DarkGray),
+                            },
+                        };
+                        let mut style = Style::default().fg(color);
+                        if line.contains(&(x, y)) {
+                            style = style.bg(Color::Green);
+                        }
+                        if pending.contains(&(x, y)) {
+                            style = style.bg(Color::Yellow);
+                        }
+                        if *cursor == (x, y) {
+                            style = style.add_modifier(Modifier::REVERSED);
+                        }
+                        Span::styled(text, style)
+                    })
+                    .collect();
+                rows.push(Spans::from(cells));
+            }
+            rows.push(Spans::from(""));
+            match &entry.game {
+                AnyGame::Classic(game) if game.order_and_chaos => {
+                    rows.push(Spans::from(format!("Placing {symbol:?}")))
+                }
+                AnyGame::Numerical(_) => {
+                    if let Some(n) = chosen_number(&entry.game, *number) {
+                        rows.push(Spans::from(format!("Placing {n}")))
+                    }
+                }
+                _ => {}
+            }
+            let board = Paragraph::new(rows).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Game #{id}")),
+            );
+            f.render_widget(board, chunks[0]);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Minimal engine for the protocol in `tictac::engine`: wins when it can,
+//! otherwise plays the first legal move.
+//!
+//! `cargo run -p tictac --example first_engine`
+
+use std::io::{self, BufRead, Write};
+
+use tictac::{
+    engine::{parse_command, whole_turn, Command, Reply},
+    AnyGame, Game, GameResult,
+};
+
+fn pick(game: &AnyGame) -> Option<tictac::Move> {
+    let moves = game.legal_moves();
+    let wins = moves.iter().find(|mv| {
+        let mut next = game.clone();
+        next.apply(mv) && matches!(next.result(), GameResult::Won(_))
+    });
+    wins.or_else(|| moves.first()).cloned()
+}
+
+fn main() -> io::Result<()> {
+    let mut out = io::stdout();
+    let mut position = None;
+    for line in io::stdin().lock().lines() {
+        let reply = match parse_command(&line?) {
+            Ok(Some(Command::Tei)) => {
+                writeln!(out, "{}", Reply::Name("first".to_owned()))?;
+                Reply::TeiOk
+            }
+            Ok(Some(Command::IsReady)) => Reply::ReadyOk,
+            Ok(Some(Command::Position(game))) => {
+                position = Some(*game);
+                continue;
+            }
+            Ok(Some(Command::Go { .. })) => match &position {
+                Some(game) => Reply::BestMove(whole_turn(game, pick)),
+                None => Reply::Info("no position".to_owned()),
+            },
+            Ok(Some(Command::Quit)) => return Ok(()),
+            Ok(_) => continue,
+            Err(e) => Reply::Info(e.to_string()),
+        };
+        writeln!(out, "{reply}")?;
+        out.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,727 @@
+//! Request and response bodies of the game server, shared with its clients.
+
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    classic::{Cell, TicTacToeGame},
+    game::{AnyGame, Game, GameKind, GameResult, Move},
+    numerical::NumericalGame,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct UserData {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct RegistrationResult {
+    pub id: i32,
+    pub username: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct PasswordChange {
+    #[serde(rename = "oldPassword")]
+    pub old_password: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct RenameData {
+    pub username: String,
+}
+
+/// Deleting an account takes the password once more
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DeleteAccountData {
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct EmailData {
+    pub email: String,
+}
+
+/// A token from a verification or password reset mail
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct MailToken {
+    pub token: String,
+}
+
+/// Asks for a password reset mail for the account with this username or
+/// verified address
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ForgotPassword {
+    pub login: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct PasswordReset {
+    pub token: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
+
+/// What `/user/login` answers with the right password
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(tag = "status")]
+pub enum LoginResult {
+    #[serde(rename = "loggedIn")]
+    LoggedIn(TokenPair),
+    /// The account has two-factor authentication: send a code along with the
+    /// ticket to `/user/login/second-factor`
+    #[serde(rename = "secondFactor")]
+    SecondFactor { ticket: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct SecondFactorData {
+    pub ticket: String,
+    /// A TOTP code or one of the recovery codes
+    pub code: String,
+}
+
+/// A TOTP secret waiting to be confirmed with a code
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TotpEnrollment {
+    /// Base32, for typing into an authenticator app
+    pub secret: String,
+    /// `otpauth://` URI, for scanning
+    pub uri: String,
+    /// The URI as a QR code
+    #[serde(rename = "qrSvg")]
+    pub qr_svg: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TotpCode {
+    pub code: String,
+}
+
+/// What logging in or refreshing hands out. The access token goes in the
+/// `Authorization` header; the refresh token buys a new pair once it expires.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TokenPair {
+    #[serde(rename = "accessToken")]
+    pub access_token: String,
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+    /// Seconds until the access token expires
+    #[serde(rename = "expiresIn")]
+    pub expires_in: i64,
+}
+
+/// A public key in the JWKS format of RFC 8037
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub usage: String,
+    pub kid: String,
+    pub x: String,
+}
+
+/// The keys that verify access tokens, from `/.well-known/jwks.json`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct RefreshData {
+    #[serde(rename = "refreshToken")]
+    pub refresh_token: String,
+}
+
+/// A login of the user, on some device
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct SessionInfo {
+    pub id: i32,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: i64,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: i64,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Coord {
+    pub x: usize,
+    pub y: usize,
+    /// Symbol for this stone, if not the turn's own
+    #[serde(default)]
+    pub symbol: Option<Cell>,
+    /// Number for this stone, if not the turn's own
+    #[serde(default)]
+    pub number: Option<u32>,
+}
+
+impl Coord {
+    pub fn at(x: usize, y: usize) -> Self {
+        Self {
+            x,
+            y,
+            symbol: None,
+            number: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TurnData {
+    pub x: usize,
+    pub y: usize,
+    /// Further stones placed in the same move, e.g. the second stone in Connect6
+    #[serde(default)]
+    pub extra: Vec<Coord>,
+    /// Symbol to place; only Order and Chaos lets a player pick another one than their own.
+    /// Stones in `extra` use it unless they name their own.
+    #[serde(default)]
+    pub symbol: Option<Cell>,
+    /// Number to place in numerical tic-tac-toe, likewise
+    #[serde(default)]
+    pub number: Option<u32>,
+}
+
+impl TurnData {
+    /// A single-stone turn
+    pub fn at(x: usize, y: usize) -> Self {
+        Self {
+            x,
+            y,
+            extra: Vec::new(),
+            symbol: None,
+            number: None,
+        }
+    }
+
+    pub fn moves(&self) -> Vec<Move> {
+        let first = Move {
+            symbol: self.symbol,
+            number: self.number,
+            ..Move::at(self.x, self.y)
+        };
+        let extra = self.extra.iter().map(|c| Move {
+            symbol: c.symbol.or(self.symbol),
+            number: c.number.or(self.number),
+            ..Move::at(c.x, c.y)
+        });
+        std::iter::once(first).chain(extra).collect()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ResetData {
+    #[serde(default)]
+    pub kind: GameKind,
+    pub size: usize,
+    pub criteria: usize,
+    /// Stones to place on each turn; the last entry repeats. `[1, 2]` is Connect6.
+    #[serde(rename = "stonesPerTurn", default = "default_stones_per_turn")]
+    pub stones_per_turn: Vec<usize>,
+    /// Pieces per player before the oldest one disappears (rolling variant)
+    #[serde(rename = "maxPieces", default)]
+    pub max_pieces: Option<usize>,
+    /// Players only see their own pieces until the game is over
+    #[serde(default)]
+    pub phantom: bool,
+    /// The X seat plays Order and the O seat plays Chaos
+    #[serde(rename = "orderAndChaos", default)]
+    pub order_and_chaos: bool,
+}
+
+fn default_stones_per_turn() -> Vec<usize> {
+    vec![1]
+}
+
+impl ResetData {
+    /// Classic rules on a `size` board
+    pub fn classic(size: usize, criteria: usize) -> Self {
+        Self {
+            kind: GameKind::Classic,
+            size,
+            criteria,
+            stones_per_turn: default_stones_per_turn(),
+            max_pieces: None,
+            phantom: false,
+            order_and_chaos: false,
+        }
+    }
+
+    pub fn to_game(&self) -> TicTacToeGame {
+        let mut game = TicTacToeGame::new(self.size, self.criteria);
+        game.stones_per_turn = self.stones_per_turn.clone();
+        game.max_pieces = self.max_pieces;
+        game.phantom = self.phantom;
+        game.order_and_chaos = self.order_and_chaos;
+        game
+    }
+
+    pub fn to_any_game(&self) -> AnyGame {
+        match self.kind {
+            GameKind::Classic => AnyGame::Classic(self.to_game()),
+            GameKind::Numerical => AnyGame::Numerical(NumericalGame::new(self.size)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct RegisterData {
+    #[serde(rename = "as")]
+    pub side: Cell,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct BotData {
+    pub username: String,
+}
+
+/// A new bot account. The API key is only ever shown here.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct BotCreated {
+    pub id: i32,
+    pub username: String,
+    #[serde(rename = "apiKey")]
+    pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ChallengeData {
+    pub opponent: String,
+    pub rules: ResetData,
+    /// Seat the challenger takes
+    #[serde(rename = "as")]
+    pub side: Cell,
+    #[serde(default)]
+    pub rated: bool,
+}
+
+/// An invitation to a game, waiting for the opponent to accept it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Challenge {
+    pub id: u64,
+    pub from: String,
+    #[serde(rename = "fromBot")]
+    pub from_bot: bool,
+    pub to: String,
+    pub rules: ResetData,
+    /// Seat the challenger takes
+    #[serde(rename = "as")]
+    pub side: Cell,
+    pub rated: bool,
+}
+
+/// What an account may do beyond playing, each role including the ones
+/// before it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+// Told apart from the players' roles in Order and Chaos in the API schema
+#[cfg_attr(feature = "schemars", schemars(rename = "UserRole"))]
+#[serde(rename_all = "camelCase")]
+pub enum Role {
+    User,
+    /// Ends games and bans users
+    Moderator,
+    /// Also deletes games, hands out roles and sees server stats
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Role> {
+        [Role::User, Role::Moderator, Role::Admin]
+            .into_iter()
+            .find(|r| r.as_str() == role)
+    }
+}
+
+/// An account as moderators see it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct UserSummary {
+    pub id: i32,
+    pub username: String,
+    pub role: Role,
+    #[serde(rename = "isBot")]
+    pub is_bot: bool,
+    #[serde(rename = "ownerId")]
+    pub owner_id: Option<i32>,
+    /// Unix timestamp the ban runs out at
+    #[serde(rename = "bannedUntil")]
+    pub banned_until: Option<i64>,
+    #[serde(rename = "mutedUntil")]
+    pub muted_until: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct RoleData {
+    pub role: Role,
+}
+
+/// A ban or a mute
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct SanctionData {
+    /// Without it, the sanction lasts until lifted
+    pub days: Option<u32>,
+    /// Kept in the moderation log
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum ReportReason {
+    Abuse,
+    Stalling,
+    Cheating,
+}
+
+impl ReportReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportReason::Abuse => "abuse",
+            ReportReason::Stalling => "stalling",
+            ReportReason::Cheating => "cheating",
+        }
+    }
+
+    pub fn parse(reason: &str) -> Option<ReportReason> {
+        [
+            ReportReason::Abuse,
+            ReportReason::Stalling,
+            ReportReason::Cheating,
+        ]
+        .into_iter()
+        .find(|r| r.as_str() == reason)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum ReportStatus {
+    /// Waiting in the moderation queue
+    Open,
+    /// A moderator acted on it
+    Resolved,
+    /// A moderator found nothing to act on
+    Dismissed,
+}
+
+impl ReportStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportStatus::Open => "open",
+            ReportStatus::Resolved => "resolved",
+            ReportStatus::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<ReportStatus> {
+        [
+            ReportStatus::Open,
+            ReportStatus::Resolved,
+            ReportStatus::Dismissed,
+        ]
+        .into_iter()
+        .find(|s| s.as_str() == status)
+    }
+}
+
+/// A complaint about the opponent in a game
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ReportData {
+    pub reason: ReportReason,
+    pub comment: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Report {
+    pub id: i32,
+    pub reporter: String,
+    pub reported: String,
+    #[serde(rename = "gameId")]
+    pub game_id: u64,
+    pub reason: ReportReason,
+    pub comment: Option<String>,
+    /// The game as it stood when it was reported
+    #[serde(rename = "gameRecord")]
+    pub game_record: Option<String>,
+    pub status: ReportStatus,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "handledBy")]
+    pub handled_by: Option<String>,
+}
+
+/// How a moderator closes a report
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ReportDecision {
+    /// `resolved` or `dismissed`
+    pub status: ReportStatus,
+    pub note: Option<String>,
+}
+
+/// An entry of the moderation log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ModerationAction {
+    pub id: i32,
+    pub moderator: String,
+    pub action: String,
+    pub target: Option<String>,
+    #[serde(rename = "gameId")]
+    pub game_id: Option<u64>,
+    #[serde(rename = "reportId")]
+    pub report_id: Option<i32>,
+    pub detail: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ServerStats {
+    pub users: u64,
+    pub bots: u64,
+    #[serde(rename = "bannedUsers")]
+    pub banned_users: u64,
+    /// Sessions that have not expired
+    pub sessions: u64,
+    pub games: usize,
+    #[serde(rename = "activeGames")]
+    pub active_games: usize,
+    #[serde(rename = "openChallenges")]
+    pub open_challenges: usize,
+    #[serde(rename = "openReports")]
+    pub open_reports: u64,
+}
+
+/// A game in the hub together with its seats
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct HubEntry {
+    pub game: AnyGame,
+    pub x_player: Option<String>,
+    pub o_player: Option<String>,
+    /// Whether the seat is taken by a bot account
+    #[serde(default)]
+    pub x_bot: bool,
+    #[serde(default)]
+    pub o_bot: bool,
+    /// Ended by a moderator before it was decided; no more turns are played
+    #[serde(default)]
+    pub aborted: bool,
+}
+
+impl HubEntry {
+    pub fn new(game: AnyGame) -> Self {
+        Self {
+            game,
+            x_player: None,
+            o_player: None,
+            x_bot: false,
+            o_bot: false,
+            aborted: false,
+        }
+    }
+
+    pub fn get_player(&self, kind: Cell) -> Option<String> {
+        match kind {
+            Cell::X => self.x_player.clone(),
+            Cell::O => self.o_player.clone(),
+        }
+    }
+
+    pub fn set_player(&mut self, kind: Cell, player: String, bot: bool) {
+        match kind {
+            Cell::X => {
+                if self.x_player.is_none() {
+                    self.x_player = Some(player);
+                    self.x_bot = bot;
+                }
+            }
+            Cell::O => {
+                if self.o_player.is_none() {
+                    self.o_player = Some(player);
+                    self.o_bot = bot;
+                }
+            }
+        }
+    }
+
+    pub fn is_bot(&self, kind: Cell) -> bool {
+        match kind {
+            Cell::X => self.x_bot,
+            Cell::O => self.o_bot,
+        }
+    }
+
+    /// The seat `player` sits in, X first if they play both
+    pub fn side_of(&self, player: &str) -> Option<Cell> {
+        if self.x_player.as_deref() == Some(player) {
+            Some(Cell::X)
+        } else if self.o_player.as_deref() == Some(player) {
+            Some(Cell::O)
+        } else {
+            None
+        }
+    }
+
+    /// The entry as seen by `viewer`, hiding what the variant keeps secret
+    pub fn view_for(&self, viewer: Option<&str>) -> HubEntry {
+        let is = |p: &Option<String>| p.is_some() && p.as_deref() == viewer;
+        let side = match (is(&self.x_player), is(&self.o_player)) {
+            (true, true) => return self.clone(),
+            (true, false) => Some(Cell::X),
+            (false, true) => Some(Cell::O),
+            (false, false) => None,
+        };
+        HubEntry {
+            game: self.game.view_for(side),
+            ..self.clone()
+        }
+    }
+
+    /// Whether no more turns will be played
+    pub fn is_over(&self) -> bool {
+        self.aborted || self.game.result() != GameResult::Ongoing
+    }
+
+    pub fn is_a_player(&self, player: String) -> bool {
+        if let Some(p) = self.x_player.clone() {
+            p == player
+        } else if let Some(p) = self.o_player.clone() {
+            p == player
+        } else {
+            false
+        }
+    }
+
+    pub fn turn(&mut self, moves: &[Move], player_name: String) {
+        let next = self.game.current_player();
+        if next.is_none() || self.aborted {
+            return;
+        }
+        let next = next.unwrap();
+        let supposed_player = self.get_player(next);
+        if supposed_player.is_none() || supposed_player.unwrap() != player_name {
+            return;
+        }
+        self.game.play_turn(moves);
+    }
+
+    /// Starts over with `game`. Aborted games stay as they are, they are
+    /// what the moderators looked at.
+    pub fn reset(&mut self, game: AnyGame, player_name: String) {
+        if self.aborted || !game.is_playable() || !self.is_a_player(player_name) {
+            return;
+        }
+        self.game = game;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Coord, HubEntry, TurnData};
+    use crate::{
+        classic::Cell,
+        game::{AnyGame, Game},
+        TicTacToeGame,
+    };
+
+    #[test]
+    fn extra_stones_can_carry_their_own_symbol() {
+        let mut game = TicTacToeGame::new(5, 5);
+        game.order_and_chaos = true;
+        game.stones_per_turn = vec![1, 2];
+        assert!(game.play_turn(&TurnData::at(0, 0).moves()));
+
+        let data = TurnData {
+            symbol: Some(Cell::X),
+            extra: vec![
+                Coord {
+                    symbol: Some(Cell::O),
+                    ..Coord::at(1, 1)
+                },
+                Coord::at(2, 2),
+            ],
+            ..TurnData::at(0, 1)
+        };
+        let moves = data.moves();
+        let symbols: Vec<_> = moves.iter().map(|mv| mv.symbol).collect();
+        assert_eq!(symbols, [Some(Cell::X), Some(Cell::O), Some(Cell::X)]);
+
+        assert!(game.play_turn(&moves[..2]));
+        assert_eq!(game.field[0][1], Some(Cell::X));
+        assert_eq!(game.field[1][1], Some(Cell::O));
+    }
+
+    #[test]
+    fn aborted_games_cannot_be_reset() {
+        let mut entry = HubEntry::new(AnyGame::Classic(TicTacToeGame::new(3, 3)));
+        entry.set_player(Cell::X, "alice".to_owned(), false);
+        entry.turn(&TurnData::at(1, 1).moves(), "alice".to_owned());
+        let bigger = || AnyGame::Classic(TicTacToeGame::new(4, 4));
+
+        entry.reset(bigger(), "bob".to_owned());
+        assert_eq!(entry.game.size(), 3);
+        entry.aborted = true;
+        entry.reset(bigger(), "alice".to_owned());
+        assert_eq!(entry.game.size(), 3);
+        assert!(entry.is_over());
+
+        entry.aborted = false;
+        entry.reset(bigger(), "alice".to_owned());
+        assert_eq!(entry.game.size(), 4);
+    }
+}
@@ -0,0 +1,818 @@
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Game, GameResult, Move};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum Cell {
+    X,
+    O,
+}
+
+impl Cell {
+    pub fn flip(self) -> Self {
+        match self {
+            Self::X => Self::O,
+            Self::O => Self::X,
+        }
+    }
+}
+
+/// Sides in Order and Chaos. Order sits in the X seat and Chaos in the O seat,
+/// but both may place either symbol.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum Role {
+    Order,
+    Chaos,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct TicTacToeGame {
+    pub field: Vec<Vec<Option<Cell>>>,
+    #[cfg_attr(feature = "serde", serde(rename = "currentPlayer"))]
+    pub current_player: Option<Cell>,
+    pub winner: Option<Cell>,
+    pub size: usize,
+    pub criteria: usize,
+    pub draw: bool,
+    #[cfg_attr(feature = "serde", serde(rename = "stonesPerTurn"))]
+    pub stones_per_turn: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(rename = "turnNumber"))]
+    pub turn_number: usize,
+    #[cfg_attr(feature = "serde", serde(rename = "stonesPlaced"))]
+    pub stones_placed: usize,
+    /// Pieces each player may keep on the board; placing one more removes the
+    /// player's oldest piece. `None` is the classic game.
+    #[cfg_attr(feature = "serde", serde(rename = "maxPieces"))]
+    pub max_pieces: Option<usize>,
+    /// Index of the move that put the piece on each cell
+    #[cfg_attr(feature = "serde", serde(rename = "placedAt"))]
+    pub placed_at: Vec<Vec<Option<usize>>>,
+    #[cfg_attr(feature = "serde", serde(rename = "moveCount"))]
+    pub move_count: usize,
+    /// Cells cleared during the current (or just finished) turn
+    #[cfg_attr(feature = "serde", serde(rename = "lastRemoved"))]
+    pub last_removed: Vec<(usize, usize)>,
+    /// Fog of war: players only see their own pieces
+    pub phantom: bool,
+    /// Hidden opponent pieces a player has bumped into, as (player, x, y)
+    pub revealed: Vec<(Cell, usize, usize)>,
+    /// Order wins with `criteria` of one symbol in a row, Chaos wins on a full board
+    #[cfg_attr(feature = "serde", serde(rename = "orderAndChaos"))]
+    pub order_and_chaos: bool,
+    #[cfg_attr(feature = "serde", serde(rename = "winningRole"))]
+    pub winning_role: Option<Role>,
+    /// Cells of the line that won the game, `None` when it was not won on
+    /// the board
+    #[cfg_attr(feature = "serde", serde(rename = "winningLine"))]
+    pub winning_line: Option<Vec<(usize, usize)>>,
+    pub history: Vec<Move>,
+}
+
+impl TicTacToeGame {
+    pub fn new(size: usize, criteria: usize) -> Self {
+        let field = vec![vec![None; size]; size];
+        Self {
+            placed_at: vec![vec![None; size]; size],
+            field,
+            current_player: Some(Cell::X),
+            winner: None,
+            size,
+            criteria,
+            draw: false,
+            stones_per_turn: vec![1],
+            turn_number: 0,
+            stones_placed: 0,
+            max_pieces: None,
+            move_count: 0,
+            last_removed: Vec::new(),
+            phantom: false,
+            revealed: Vec::new(),
+            order_and_chaos: false,
+            winning_role: None,
+            winning_line: None,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        if self.size == 0 || self.criteria > self.size || self.stones_per_turn.contains(&0) {
+            return false;
+        }
+        // Revealed cells would go stale once the piece behind them disappears
+        if self.phantom && self.max_pieces.is_some() {
+            return false;
+        }
+        // Pieces don't belong to a player in Order and Chaos
+        if self.order_and_chaos && (self.phantom || self.max_pieces.is_some()) {
+            return false;
+        }
+        match self.max_pieces {
+            // Both players together must never be able to fill the board, and a
+            // turn must not remove stones placed during that same turn
+            Some(n) => {
+                2 * n < self.size * self.size
+                    && self.stones_per_turn.iter().all(|&stones| stones <= n)
+            }
+            None => true,
+        }
+    }
+
+    /// Number of stones the current player has to place this turn. The last
+    /// entry of `stones_per_turn` repeats for every following turn.
+    pub fn stones_required(&self) -> usize {
+        self.stones_per_turn
+            .get(self.turn_number)
+            .or_else(|| self.stones_per_turn.last())
+            .copied()
+            .unwrap_or(1)
+    }
+
+    pub fn role_of(&self, player: Cell) -> Option<Role> {
+        if !self.order_and_chaos {
+            return None;
+        }
+        Some(match player {
+            Cell::X => Role::Order,
+            Cell::O => Role::Chaos,
+        })
+    }
+
+    pub fn turn(&mut self, x: usize, y: usize) -> Option<Cell> {
+        let symbol = self.current_player?;
+        self.turn_as(x, y, symbol)
+    }
+
+    /// Places `symbol` for the current player. Outside of Order and Chaos the
+    /// symbol has to be the player's own.
+    pub fn turn_as(&mut self, x: usize, y: usize, symbol: Cell) -> Option<Cell> {
+        if self.winner.is_some() || self.draw {
+            return None;
+        }
+        if !self.order_and_chaos && Some(symbol) != self.current_player {
+            return None;
+        }
+        if self.field.get(x)?.get(y)?.is_some() {
+            self.reject_occupied(x, y);
+            return None;
+        }
+        if self.stones_placed == 0 {
+            self.last_removed.clear();
+        }
+        self.field[x][y] = Some(symbol);
+        self.placed_at[x][y] = Some(self.move_count);
+        self.move_count += 1;
+        self.history.push(Move {
+            symbol: Some(symbol),
+            ..Move::at(x, y)
+        });
+        if let Some(max) = self.max_pieces {
+            if self.pieces_of(self.current_player?).len() > max {
+                self.remove_oldest(self.current_player?);
+            }
+        }
+        let winner = self.check_field_for_winner();
+        if winner.is_some() {
+            self.winner = if self.order_and_chaos {
+                Some(Cell::X)
+            } else {
+                self.current_player
+            };
+            self.winning_role = self.role_of(self.winner?);
+            self.winning_line = self.find_winning_line();
+            return self.winner;
+        }
+        self.stones_placed += 1;
+        if self.stones_placed >= self.stones_required() {
+            self.current_player = Some(self.current_player?.flip());
+            self.turn_number += 1;
+            self.stones_placed = 0;
+        }
+        self.draw = self.check_draw();
+        if self.draw && self.order_and_chaos {
+            self.draw = false;
+            self.winner = Some(Cell::O);
+            self.winning_role = Some(Role::Chaos);
+            return self.winner;
+        }
+        None
+    }
+
+    /// Places all of `cells` as a single move. The move is applied only if
+    /// every cell is free and it completes the current player's turn (or
+    /// ends the game), otherwise the game is left untouched.
+    pub fn play(&mut self, cells: &[(usize, usize)]) -> Option<Cell> {
+        let symbol = self.current_player?;
+        self.play_as(cells, symbol)
+    }
+
+    /// [`Self::play`] with an explicit symbol, see [`Self::turn_as`]
+    pub fn play_as(&mut self, cells: &[(usize, usize)], symbol: Cell) -> Option<Cell> {
+        let stones: Vec<_> = cells.iter().map(|&(x, y)| (x, y, symbol)).collect();
+        self.play_stones(&stones)
+    }
+
+    /// [`Self::play`] with a symbol per stone
+    pub fn play_stones(&mut self, stones: &[(usize, usize, Cell)]) -> Option<Cell> {
+        if stones.is_empty() {
+            return None;
+        }
+        let mut next = self.clone();
+        for (i, &(x, y, symbol)) in stones.iter().enumerate() {
+            if next.winner.is_some() || next.draw {
+                return None;
+            }
+            if next.field.get(x)?.get(y)?.is_some() {
+                self.reject_occupied(x, y);
+                return None;
+            }
+            if i > 0 && next.stones_placed == 0 {
+                return None;
+            }
+            if !self.order_and_chaos && Some(symbol) != next.current_player {
+                return None;
+            }
+            next.turn_as(x, y, symbol);
+        }
+        if next.stones_placed != 0 && next.winner.is_none() && !next.draw {
+            return None;
+        }
+        *self = next;
+        self.winner
+    }
+
+    pub fn check_field_for_winner(&self) -> Option<Cell> {
+        for i in 0..self.size - self.criteria + 1 {
+            for j in 0..self.size - self.criteria + 1 {
+                let x = self.check_span_for_winner(i, j);
+                if x.is_some() {
+                    return x;
+                }
+            }
+        }
+        None
+    }
+
+    /// Cells of the first line of `criteria` equal symbols, if there is one
+    pub fn find_winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        let n = self.size as isize;
+        let len = self.criteria as isize;
+        for x in 0..n {
+            for y in 0..n {
+                for (dx, dy) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                    let (end_x, end_y) = (x + dx * (len - 1), y + dy * (len - 1));
+                    if len == 0 || end_x >= n || end_y < 0 || end_y >= n {
+                        continue;
+                    }
+                    let line: Vec<_> = (0..len)
+                        .map(|i| ((x + dx * i) as usize, (y + dy * i) as usize))
+                        .collect();
+                    let first = self.field[x as usize][y as usize];
+                    if first.is_some() && line.iter().all(|&(i, j)| self.field[i][j] == first) {
+                        return Some(line);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn check_span_for_winner(&self, x: usize, y: usize) -> Option<Cell> {
+        // Check for rows
+        for i in x..x + self.criteria {
+            let mut q: Option<Cell> = None;
+            for j in 0..self.criteria {
+                if j == 0 {
+                    q = self.field[i][y + j];
+                    if q.is_none() {
+                        break;
+                    }
+                } else if self.field[i][y + j] != q {
+                    q = None;
+                    break;
+                }
+            }
+            if q.is_some() {
+                return q;
+            }
+        }
+
+        for i in y..y + self.criteria {
+            let mut q: Option<Cell> = None;
+            for j in 0..self.criteria {
+                if j == 0 {
+                    q = self.field[x + j][i];
+                    if q.is_none() {
+                        break;
+                    }
+                } else if self.field[x + j][i] != q {
+                    q = None;
+                    break;
+                }
+            }
+            if q.is_some() {
+                return q;
+            }
+        }
+
+        let mut q: Option<Cell> = None;
+        for i in 0..self.criteria {
+            if i == 0 {
+                q = self.field[x + i][y + i];
+                if q.is_none() {
+                    break;
+                }
+            } else if self.field[x + i][y + i] != q {
+                q = None;
+                break;
+            }
+        }
+        if q.is_some() {
+            return q;
+        }
+
+        let mut q: Option<Cell> = None;
+        for i in 0..self.criteria {
+            if i == 0 {
+                q = self.field[x + i][y + self.criteria - 1 - i];
+                if q.is_none() {
+                    break;
+                }
+            } else if self.field[x + i][y + self.criteria - 1 - i] != q {
+                q = None;
+                break;
+            }
+        }
+        if q.is_some() {
+            return q;
+        }
+
+        None
+    }
+
+    /// What `viewer` is allowed to see of the game. In phantom games players
+    /// only see their own pieces and the ones they have bumped into, while
+    /// spectators (`None`) see nothing until the game is over.
+    pub fn view_for(&self, viewer: Option<Cell>) -> Self {
+        let mut view = self.clone();
+        if !self.hides_information() {
+            return view;
+        }
+        view.revealed.retain(|&(who, _, _)| Some(who) == viewer);
+        view.history
+            .retain(|mv| viewer.is_some() && mv.symbol == viewer);
+        for i in 0..self.size {
+            for j in 0..self.size {
+                let visible = viewer.is_some()
+                    && (self.field[i][j] == viewer
+                        || view.revealed.iter().any(|&(_, x, y)| (x, y) == (i, j)));
+                if !visible {
+                    view.field[i][j] = None;
+                    view.placed_at[i][j] = None;
+                }
+            }
+        }
+        view
+    }
+
+    /// A move onto an opponent's hidden piece is rejected, but the player
+    /// gets to know the piece is there
+    fn reject_occupied(&mut self, x: usize, y: usize) {
+        if !self.phantom {
+            return;
+        }
+        if let (Some(player), Some(owner)) = (self.current_player, self.field[x][y]) {
+            if owner != player && !self.revealed.contains(&(player, x, y)) {
+                self.revealed.push((player, x, y));
+            }
+        }
+    }
+
+    fn pieces_of(&self, kind: Cell) -> Vec<(usize, usize)> {
+        let mut pieces = Vec::new();
+        for (i, row) in self.field.iter().enumerate() {
+            for (j, cell) in row.iter().enumerate() {
+                if *cell == Some(kind) {
+                    pieces.push((i, j));
+                }
+            }
+        }
+        pieces
+    }
+
+    fn remove_oldest(&mut self, kind: Cell) {
+        let oldest = self
+            .pieces_of(kind)
+            .into_iter()
+            .min_by_key(|&(x, y)| self.placed_at[x][y]);
+        if let Some((x, y)) = oldest {
+            self.field[x][y] = None;
+            self.placed_at[x][y] = None;
+            self.last_removed.push((x, y));
+        }
+    }
+
+    pub(crate) fn check_draw(&self) -> bool {
+        // Pieces keep disappearing in the rolling variant, so the board never fills up
+        if self.max_pieces.is_some() {
+            return false;
+        }
+        self.field.iter().all(|x| x.iter().all(|y| y.is_some()))
+    }
+}
+
+impl Game for TicTacToeGame {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn is_valid(&self) -> bool {
+        TicTacToeGame::is_valid(self)
+    }
+
+    fn current_player(&self) -> Option<Cell> {
+        self.current_player
+    }
+
+    fn result(&self) -> GameResult {
+        match self.winner {
+            Some(winner) => GameResult::Won(winner),
+            None if self.draw => GameResult::Draw,
+            None => GameResult::Ongoing,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        if self.result() != GameResult::Ongoing {
+            return moves;
+        }
+        let symbols = if self.order_and_chaos {
+            vec![Cell::X, Cell::O]
+        } else {
+            self.current_player.into_iter().collect()
+        };
+        for (x, row) in self.field.iter().enumerate() {
+            for (y, cell) in row.iter().enumerate() {
+                if cell.is_none() {
+                    for &symbol in &symbols {
+                        moves.push(Move {
+                            symbol: Some(symbol),
+                            ..Move::at(x, y)
+                        });
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    fn winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        self.winning_line.clone()
+    }
+
+    fn apply(&mut self, mv: &Move) -> bool {
+        let before = self.move_count;
+        if let Some(symbol) = mv.symbol.or(self.current_player) {
+            self.turn_as(mv.x, mv.y, symbol);
+        }
+        self.move_count != before
+    }
+
+    fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    fn play_turn(&mut self, moves: &[Move]) -> bool {
+        // Goes through `play_stones` so phantom games remember revealed cells
+        // even when the move is rejected
+        let before = self.move_count;
+        let stones: Option<Vec<_>> = moves
+            .iter()
+            .map(|mv| Some((mv.x, mv.y, mv.symbol.or(self.current_player)?)))
+            .collect();
+        if let Some(stones) = stones {
+            self.play_stones(&stones);
+        }
+        self.move_count != before
+    }
+
+    fn hides_information(&self) -> bool {
+        self.phantom && self.result() == GameResult::Ongoing
+    }
+
+    fn view_for(&self, viewer: Option<Cell>) -> Self {
+        TicTacToeGame::view_for(self, viewer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Cell::*;
+    use super::{Role, TicTacToeGame};
+    use crate::game::{Game, GameResult, Move, MAX_SIZE};
+
+    #[test]
+    fn playable_needs_valid_rules_and_a_hostable_size() {
+        assert!(TicTacToeGame::new(3, 3).is_playable());
+        assert!(TicTacToeGame::new(MAX_SIZE, 5).is_playable());
+        assert!(!TicTacToeGame::new(MAX_SIZE + 1, 5).is_playable());
+        assert!(!TicTacToeGame::new(3, 4).is_playable());
+    }
+
+    #[test]
+    fn detects_horizontal() {
+        let mut x = TicTacToeGame::new(3, 2);
+        x.field = vec![
+            vec![None, None, None],
+            vec![None, None, None],
+            vec![None, None, None],
+        ];
+        assert_eq!(None, x.check_span_for_winner(0, 0));
+        assert_eq!(None, x.check_field_for_winner());
+        for i in 0..3 {
+            for j in 0..2 {
+                x.field = vec![
+                    vec![None, None, None],
+                    vec![None, None, None],
+                    vec![None, None, None],
+                ];
+                x.field[i][j] = Some(X);
+                x.field[i][j + 1] = Some(X);
+                // assert_eq!(Some(X), x.check_span_for_winner(i, j), "check_span_for_winner({i}, {j}) (field: {:?})", x.field);
+                assert_eq!(
+                    Some(X),
+                    x.check_field_for_winner(),
+                    "check_field_for_winner (i={i}, j={j}, field={:?})",
+                    x.field
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn detects_vertical() {
+        let mut x = TicTacToeGame::new(3, 2);
+        x.field = vec![
+            vec![None, None, None],
+            vec![None, None, None],
+            vec![None, None, None],
+        ];
+        assert_eq!(None, x.check_span_for_winner(0, 0));
+        assert_eq!(None, x.check_field_for_winner());
+        for i in 0..2 {
+            for j in 0..3 {
+                x.field = vec![
+                    vec![None, None, None],
+                    vec![None, None, None],
+                    vec![None, None, None],
+                ];
+                x.field[i][j] = Some(X);
+                x.field[i + 1][j] = Some(X);
+                // assert_eq!(Some(X), x.check_span_for_winner(i, j), "check_span_for_winner({i}, {j}) (field: {:?})", x.field);
+                assert_eq!(
+                    Some(X),
+                    x.check_field_for_winner(),
+                    "check_field_for_winner (i={i}, j={j}, field={:?})",
+                    x.field
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn detects_diagonal() {
+        let mut x = TicTacToeGame::new(3, 2);
+        x.field = vec![
+            vec![None, None, None],
+            vec![None, None, None],
+            vec![None, None, None],
+        ];
+        assert_eq!(None, x.check_span_for_winner(0, 0));
+        assert_eq!(None, x.check_field_for_winner());
+        for i in 0..2 {
+            for j in 0..2 {
+                x.field = vec![
+                    vec![None, None, None],
+                    vec![None, None, None],
+                    vec![None, None, None],
+                ];
+                x.field[i][j] = Some(X);
+                x.field[i + 1][j + 1] = Some(X);
+                // assert_eq!(Some(X), x.check_span_for_winner(i, j), "check_span_for_winner({i}, {j}) (field: {:?})", x.field);
+                assert_eq!(
+                    Some(X),
+                    x.check_field_for_winner(),
+                    "check_field_for_winner (i={i}, j={j}, field={:?})",
+                    x.field
+                );
+
+                x.field = vec![
+                    vec![None, None, None],
+                    vec![None, None, None],
+                    vec![None, None, None],
+                ];
+                x.field[i + 1][j] = Some(X);
+                x.field[i][j + 1] = Some(X);
+                // assert_eq!(Some(X), x.check_span_for_winner(i, j), "check_span_for_winner({i}, {j}) (field: {:?})", x.field);
+                assert_eq!(
+                    Some(X),
+                    x.check_field_for_winner(),
+                    "check_field_for_winner (i={i}, j={j}, field={:?})",
+                    x.field
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn multi_stone_turns() {
+        let mut x = TicTacToeGame::new(19, 6);
+        x.stones_per_turn = vec![1, 2];
+        assert_eq!(1, x.stones_required());
+        x.turn(9, 9);
+        assert_eq!(Some(O), x.current_player);
+        assert_eq!(2, x.stones_required());
+        x.turn(0, 0);
+        assert_eq!(Some(O), x.current_player);
+        x.turn(0, 1);
+        assert_eq!(Some(X), x.current_player);
+        assert_eq!(2, x.stones_required());
+    }
+
+    #[test]
+    fn play_is_atomic() {
+        let mut x = TicTacToeGame::new(19, 6);
+        x.stones_per_turn = vec![1, 2];
+        assert_eq!(None, x.play(&[(9, 9), (9, 10)]));
+        assert_eq!(None, x.field[9][9]);
+        x.play(&[(9, 9)]);
+        assert_eq!(Some(X), x.field[9][9]);
+
+        x.play(&[(0, 0)]);
+        assert_eq!(None, x.field[0][0]);
+        x.play(&[(0, 0), (9, 9)]);
+        assert_eq!(None, x.field[0][0]);
+        x.play(&[(0, 0), (0, 1)]);
+        assert_eq!(Some(O), x.field[0][0]);
+        assert_eq!(Some(O), x.field[0][1]);
+        assert_eq!(Some(X), x.current_player);
+    }
+
+    #[test]
+    fn rolling_removes_oldest_piece() {
+        let mut x = TicTacToeGame::new(3, 3);
+        x.max_pieces = Some(3);
+        for (i, j) in [(0, 0), (1, 0), (0, 1), (1, 1), (2, 2), (2, 0)] {
+            x.turn(i, j);
+        }
+        assert!(x.last_removed.is_empty());
+        x.turn(0, 2);
+        assert_eq!(vec![(0, 0)], x.last_removed);
+        assert_eq!(None, x.field[0][0]);
+        assert_eq!(None, x.winner);
+        x.turn(2, 1);
+        assert_eq!(vec![(1, 0)], x.last_removed);
+        assert_eq!(None, x.field[1][0]);
+        assert!(!x.draw);
+    }
+
+    #[test]
+    fn rolling_rules_are_validated() {
+        let mut x = TicTacToeGame::new(3, 3);
+        x.max_pieces = Some(4);
+        assert!(x.is_valid());
+        x.max_pieces = Some(5);
+        assert!(!x.is_valid());
+    }
+
+    #[test]
+    fn phantom_hides_opponent_pieces() {
+        let mut x = TicTacToeGame::new(3, 3);
+        x.phantom = true;
+        x.turn(0, 0);
+        x.turn(1, 1);
+
+        let view = x.view_for(Some(X));
+        assert_eq!(Some(X), view.field[0][0]);
+        assert_eq!(None, view.field[1][1]);
+        let view = x.view_for(Some(O));
+        assert_eq!(None, view.field[0][0]);
+        assert_eq!(Some(O), view.field[1][1]);
+        let view = x.view_for(None);
+        assert!(view.field.iter().flatten().all(|c| c.is_none()));
+
+        // X bumps into O's piece and has to move again
+        x.turn(1, 1);
+        assert_eq!(Some(X), x.current_player);
+        assert_eq!(vec![(X, 1, 1)], x.revealed);
+        assert_eq!(Some(O), x.view_for(Some(X)).field[1][1]);
+        assert!(x.view_for(Some(O)).revealed.is_empty());
+    }
+
+    #[test]
+    fn phantom_is_revealed_after_game_end() {
+        let mut x = TicTacToeGame::new(3, 3);
+        x.phantom = true;
+        for (i, j) in [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            x.turn(i, j);
+        }
+        assert_eq!(Some(X), x.winner);
+        assert_eq!(x.field, x.view_for(None).field);
+    }
+
+    #[test]
+    fn order_wins_with_either_symbol() {
+        let mut x = TicTacToeGame::new(6, 5);
+        x.order_and_chaos = true;
+        assert_eq!(Some(Role::Order), x.role_of(X));
+        for j in 0..4 {
+            x.turn_as(0, j, O);
+            x.turn_as(5, j, X);
+        }
+        assert_eq!(None, x.winner);
+        x.turn_as(3, 3, X);
+        // Chaos completes the line, which still hands Order the win
+        assert_eq!(Some(O), x.current_player);
+        x.turn_as(0, 4, O);
+        assert_eq!(Some(X), x.winner);
+        assert_eq!(Some(Role::Order), x.winning_role);
+    }
+
+    #[test]
+    fn chaos_wins_on_full_board() {
+        let mut x = TicTacToeGame::new(3, 3);
+        x.order_and_chaos = true;
+        let cells = [[X, O, X], [X, O, O], [O, X, X]];
+        for (i, row) in cells.iter().enumerate() {
+            for (j, &symbol) in row.iter().enumerate() {
+                assert_eq!(None, x.winner);
+                x.turn_as(i, j, symbol);
+            }
+        }
+        assert!(!x.draw);
+        assert_eq!(Some(O), x.winner);
+        assert_eq!(Some(Role::Chaos), x.winning_role);
+    }
+
+    #[test]
+    fn classic_rejects_foreign_symbol() {
+        let mut x = TicTacToeGame::new(3, 3);
+        x.turn_as(0, 0, O);
+        assert_eq!(None, x.field[0][0]);
+        assert_eq!(None, x.role_of(X));
+    }
+
+    #[test]
+    fn legal_moves_cover_empty_cells() {
+        let mut x = TicTacToeGame::new(3, 3);
+        assert_eq!(9, x.legal_moves().len());
+        assert!(x.apply(&Move::at(1, 1)));
+        assert!(!x.apply(&Move::at(1, 1)));
+        assert_eq!(8, x.legal_moves().len());
+        assert!(x.legal_moves().iter().all(|mv| mv.symbol == Some(O)));
+
+        let mut x = TicTacToeGame::new(3, 3);
+        x.order_and_chaos = true;
+        assert_eq!(18, x.legal_moves().len());
+    }
+
+    #[test]
+    fn play_turn_needs_a_complete_turn() {
+        let mut x = TicTacToeGame::new(19, 6);
+        x.stones_per_turn = vec![1, 2];
+        assert!(x.play_turn(&[Move::at(9, 9)]));
+        assert!(!x.play_turn(&[Move::at(0, 0)]));
+        assert!(x.play_turn(&[Move::at(0, 0), Move::at(0, 1)]));
+        assert_eq!(GameResult::Ongoing, x.result());
+    }
+
+    #[test]
+    fn finds_winning_line() {
+        let mut x = TicTacToeGame::new(4, 3);
+        assert_eq!(None, x.find_winning_line());
+        x.field[1][3] = Some(O);
+        x.field[2][2] = Some(O);
+        x.field[3][1] = Some(O);
+        assert_eq!(Some(vec![(1, 3), (2, 2), (3, 1)]), x.find_winning_line());
+    }
+
+    #[test]
+    fn winner_carries_the_line() {
+        let mut x = TicTacToeGame::new(3, 3);
+        for (i, j) in [(0, 0), (1, 0), (1, 1), (2, 0)] {
+            x.turn(i, j);
+        }
+        assert_eq!(None, x.winning_line);
+        x.turn(2, 2);
+        assert_eq!(Some(vec![(0, 0), (1, 1), (2, 2)]), x.winning_line);
+    }
+}
@@ -0,0 +1,356 @@
+//! Text protocol for talking to engines (bot programs), much like UCI in
+//! chess. The engine reads commands from stdin and answers on stdout, one
+//! per line.
+//!
+//! Commands sent to the engine:
+//!
+//! - `tei`: sent once on startup. The engine answers with `id name <name>`,
+//!   optionally `id author <author>`, and then `teiok`.
+//! - `isready`: the engine answers `readyok` once it can take commands.
+//! - `newgame`: the following positions belong to a new game.
+//! - `position <position>`: the position to think about, in the notation of
+//!   [`crate::notation`], e.g. `position X2/1O1/3 X 3 2.0 classic`.
+//! - `go [movetime <ms>]`: find a turn for the player to move in the last
+//!   position, within `ms` milliseconds when given.
+//! - `stop`: answer `bestmove` right away.
+//! - `quit`: exit.
+//!
+//! Replies from the engine:
+//!
+//! - `id name <name>`, `id author <author>`, `teiok`, `readyok` as above.
+//! - `bestmove <move> [<move>...]`: the whole turn, one move per stone, in
+//!   the move notation of [`crate::notation`] (`1,1`, `0,2=O`, `2,2=5`).
+//! - `info <text>`: anything the engine wants to log. It is ignored.
+//!
+//! Unknown lines are ignored on both sides, so either side can be extended
+//! without breaking the other.
+
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command as Process, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    game::{AnyGame, Game, GameResult, Move},
+    notation::{self, NotationError},
+};
+
+/// A line sent to the engine
+#[derive(Clone, Debug)]
+pub enum Command {
+    Tei,
+    IsReady,
+    NewGame,
+    Position(Box<AnyGame>),
+    Go { movetime: Option<u64> },
+    Stop,
+    Quit,
+}
+
+/// A line sent by the engine
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reply {
+    Name(String),
+    Author(String),
+    TeiOk,
+    ReadyOk,
+    BestMove(Vec<Move>),
+    Info(String),
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Command::Tei => write!(f, "tei"),
+            Command::IsReady => write!(f, "isready"),
+            Command::NewGame => write!(f, "newgame"),
+            Command::Position(game) => write!(f, "position {}", notation::write_position(game)),
+            Command::Go { movetime: None } => write!(f, "go"),
+            Command::Go { movetime: Some(ms) } => write!(f, "go movetime {ms}"),
+            Command::Stop => write!(f, "stop"),
+            Command::Quit => write!(f, "quit"),
+        }
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reply::Name(name) => write!(f, "id name {name}"),
+            Reply::Author(author) => write!(f, "id author {author}"),
+            Reply::TeiOk => write!(f, "teiok"),
+            Reply::ReadyOk => write!(f, "readyok"),
+            Reply::BestMove(moves) => {
+                let moves: Vec<_> = moves.iter().map(notation::write_move).collect();
+                write!(f, "bestmove {}", moves.join(" "))
+            }
+            Reply::Info(text) => write!(f, "info {text}"),
+        }
+    }
+}
+
+/// Parses a command, `Ok(None)` for lines to ignore
+pub fn parse_command(line: &str) -> Result<Option<Command>, NotationError> {
+    let line = line.trim();
+    let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+    Ok(Some(match word {
+        "tei" => Command::Tei,
+        "isready" => Command::IsReady,
+        "newgame" => Command::NewGame,
+        "position" => Command::Position(Box::new(notation::parse_position(rest.trim())?)),
+        "go" => {
+            let mut words = rest.split_whitespace();
+            let mut movetime = None;
+            while let Some(word) = words.next() {
+                if word == "movetime" {
+                    let ms = words.next().unwrap_or_default();
+                    movetime = Some(ms.parse().map_err(|_| {
+                        NotationError(format!("expected a time in milliseconds, found `{ms}`"))
+                    })?);
+                }
+            }
+            Command::Go { movetime }
+        }
+        "stop" => Command::Stop,
+        "quit" => Command::Quit,
+        _ => return Ok(None),
+    }))
+}
+
+/// Parses a reply, `Ok(None)` for lines to ignore
+pub fn parse_reply(line: &str) -> Result<Option<Reply>, NotationError> {
+    let line = line.trim();
+    let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+    Ok(Some(match (word, rest.split_once(' ')) {
+        ("id", Some(("name", name))) => Reply::Name(name.trim().to_owned()),
+        ("id", Some(("author", author))) => Reply::Author(author.trim().to_owned()),
+        ("teiok", _) => Reply::TeiOk,
+        ("readyok", _) => Reply::ReadyOk,
+        ("bestmove", _) => Reply::BestMove(
+            rest.split_whitespace()
+                .map(notation::parse_move)
+                .collect::<Result<_, _>>()?,
+        ),
+        ("info", _) => Reply::Info(rest.to_owned()),
+        _ => return Ok(None),
+    }))
+}
+
+/// Builds a whole turn out of single moves picked by `pick`, for engines
+/// that think one stone at a time. Returns nothing once the game is over.
+pub fn whole_turn<G: Game>(game: &G, mut pick: impl FnMut(&G) -> Option<Move>) -> Vec<Move> {
+    let mut next = game.clone();
+    let mover = game.current_player();
+    let mut moves = Vec::new();
+    while next.result() == GameResult::Ongoing && next.current_player() == mover {
+        let mv = match pick(&next) {
+            Some(mv) => mv,
+            None => break,
+        };
+        if !next.apply(&mv) {
+            break;
+        }
+        moves.push(mv);
+    }
+    moves
+}
+
+/// How long an engine may take to start up, or to answer after `stop`
+const GRACE: Duration = Duration::from_secs(5);
+
+fn protocol_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// An engine running as a child process
+pub struct EngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    pub name: String,
+}
+
+impl EngineProcess {
+    /// Starts `program` and waits for its `teiok`
+    pub fn spawn(program: &str, args: &[String]) -> io::Result<Self> {
+        let mut child = Process::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, lines) = mpsc::channel();
+        // Reading blocks, so a thread forwards lines to be waited on with a timeout
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            child,
+            stdin,
+            lines,
+            name: program.to_owned(),
+        };
+        engine.send(&Command::Tei)?;
+        let deadline = Instant::now() + GRACE;
+        loop {
+            match engine.recv(deadline)? {
+                Reply::Name(name) => engine.name = name,
+                Reply::TeiOk => return Ok(engine),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn send(&mut self, command: &Command) -> io::Result<()> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()
+    }
+
+    /// The next reply that is not to be ignored, waiting until `deadline`
+    fn recv(&mut self, deadline: Instant) -> io::Result<Reply> {
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(timeout) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "engine timed out"))
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(io::Error::new(io::ErrorKind::BrokenPipe, "engine exited"))
+                }
+            };
+            match parse_reply(&line) {
+                Ok(Some(Reply::Info(_))) | Ok(None) => {}
+                Ok(Some(reply)) => return Ok(reply),
+                Err(e) => return Err(protocol_error(format!("{}: {e}", self.name))),
+            }
+        }
+    }
+
+    /// Tells the engine a new game starts and waits until it is ready
+    pub fn new_game(&mut self) -> io::Result<()> {
+        self.send(&Command::NewGame)?;
+        self.send(&Command::IsReady)?;
+        let deadline = Instant::now() + GRACE;
+        while self.recv(deadline)? != Reply::ReadyOk {}
+        Ok(())
+    }
+
+    /// Asks for a turn in `game`. An engine that overruns `movetime` is told
+    /// to stop and gets a little more time to answer.
+    pub fn best_move(&mut self, game: &AnyGame, movetime: Option<u64>) -> io::Result<Vec<Move>> {
+        self.send(&Command::Position(Box::new(game.clone())))?;
+        self.send(&Command::Go { movetime })?;
+        let mut deadline = match movetime {
+            Some(ms) => Instant::now() + Duration::from_millis(ms),
+            None => Instant::now() + Duration::from_secs(3600),
+        };
+        let mut stopped = false;
+        loop {
+            match self.recv(deadline) {
+                Ok(Reply::BestMove(moves)) => return Ok(moves),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::TimedOut && !stopped => {
+                    self.send(&Command::Stop)?;
+                    deadline = Instant::now() + GRACE;
+                    stopped = true;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        let _ = self.send(&Command::Quit);
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_command, parse_reply, whole_turn, Command, Reply};
+    use crate::{
+        game::{AnyGame, Game, Move},
+        notation, TicTacToeGame,
+    };
+
+    #[test]
+    fn commands_round_trip() {
+        let mut game = TicTacToeGame::new(3, 3);
+        game.turn(1, 1);
+        let game = AnyGame::Classic(game);
+        for command in [
+            Command::Tei,
+            Command::NewGame,
+            Command::Position(Box::new(game)),
+            Command::Go {
+                movetime: Some(500),
+            },
+            Command::Go { movetime: None },
+        ] {
+            let line = command.to_string();
+            let parsed = parse_command(&line).unwrap().unwrap();
+            assert_eq!(line, parsed.to_string());
+        }
+        assert_eq!(
+            "position 3/1X1/3 O 3 1.0 classic",
+            Command::Position(Box::new(
+                notation::parse_position("3/1X1/3 O 3 1.0 classic").unwrap()
+            ))
+            .to_string()
+        );
+        assert!(parse_command("hello").unwrap().is_none());
+        assert!(parse_command("go movetime soon").is_err());
+    }
+
+    #[test]
+    fn replies_parse() {
+        assert_eq!(
+            Some(Reply::BestMove(vec![
+                Move::at(1, 1),
+                Move {
+                    number: Some(5),
+                    ..Move::at(0, 2)
+                }
+            ])),
+            parse_reply("bestmove 1,1 0,2=5").unwrap()
+        );
+        assert_eq!(
+            Some(Reply::Name("Random Bot".to_owned())),
+            parse_reply("id name Random Bot").unwrap()
+        );
+        assert_eq!(None, parse_reply("id").unwrap());
+        assert!(parse_reply("bestmove 1").is_err());
+    }
+
+    #[test]
+    fn whole_turn_places_every_stone() {
+        let mut game = TicTacToeGame::new(5, 4);
+        game.stones_per_turn = vec![1, 2];
+        let first = |g: &TicTacToeGame| g.legal_moves().into_iter().next();
+        assert_eq!(1, whole_turn(&game, first).len());
+        game.play(&[(0, 0)]);
+        let turn = whole_turn(&game, first);
+        assert_eq!(2, turn.len());
+        assert!(game.play_turn(&turn));
+    }
+}
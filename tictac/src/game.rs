@@ -0,0 +1,209 @@
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    classic::{Cell, TicTacToeGame},
+    numerical::NumericalGame,
+};
+
+/// A single placement. Turns that take several stones are made of several moves
+/// by the same player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Move {
+    pub x: usize,
+    pub y: usize,
+    /// Symbol to place when the rules let the player choose one
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub symbol: Option<Cell>,
+    /// Number to place in numerical tic-tac-toe
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub number: Option<u32>,
+}
+
+impl Move {
+    pub fn at(x: usize, y: usize) -> Self {
+        Self {
+            x,
+            y,
+            symbol: None,
+            number: None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum GameResult {
+    Ongoing,
+    Won(Cell),
+    Draw,
+}
+
+/// Largest board the server hosts
+pub const MAX_SIZE: usize = 100;
+
+/// Rules of a game the hub can host
+pub trait Game: Clone {
+    fn size(&self) -> usize;
+
+    fn is_valid(&self) -> bool;
+
+    /// Valid, and small enough for the server to host
+    fn is_playable(&self) -> bool {
+        self.size() <= MAX_SIZE && self.is_valid()
+    }
+
+    fn current_player(&self) -> Option<Cell>;
+
+    fn result(&self) -> GameResult;
+
+    /// Every move the current player could make next
+    fn legal_moves(&self) -> Vec<Move>;
+
+    /// Applies a single move, returning whether it was legal
+    fn apply(&mut self, mv: &Move) -> bool;
+
+    /// Moves played so far, oldest first
+    fn history(&self) -> &[Move];
+
+    /// Cells that decided the game, if it has been won on the board. Games
+    /// keep it in their state so clients don't have to scan the board.
+    fn winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        None
+    }
+
+    /// Applies `moves` as one turn: either all of them are legal and together
+    /// complete the current player's turn (or end the game), or nothing happens
+    fn play_turn(&mut self, moves: &[Move]) -> bool {
+        let player = self.current_player();
+        let mut next = self.clone();
+        for (i, mv) in moves.iter().enumerate() {
+            if i > 0 && (next.current_player() != player || next.result() != GameResult::Ongoing) {
+                return false;
+            }
+            if !next.apply(mv) {
+                return false;
+            }
+        }
+        if moves.is_empty()
+            || (next.current_player() == player && next.result() == GameResult::Ongoing)
+        {
+            return false;
+        }
+        *self = next;
+        true
+    }
+
+    /// Whether some players can't see the whole game right now
+    fn hides_information(&self) -> bool {
+        false
+    }
+
+    /// What `viewer` (a player, or a spectator for `None`) is allowed to see
+    fn view_for(&self, _viewer: Option<Cell>) -> Self {
+        self.clone()
+    }
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum GameKind {
+    #[default]
+    Classic,
+    Numerical,
+}
+
+/// Any of the rule engines the hub can host
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+#[cfg_attr(feature = "serde", serde(tag = "kind"))]
+pub enum AnyGame {
+    Classic(TicTacToeGame),
+    Numerical(NumericalGame),
+}
+
+impl Game for AnyGame {
+    fn size(&self) -> usize {
+        match self {
+            Self::Classic(game) => game.size(),
+            Self::Numerical(game) => game.size(),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match self {
+            Self::Classic(game) => Game::is_valid(game),
+            Self::Numerical(game) => Game::is_valid(game),
+        }
+    }
+
+    fn current_player(&self) -> Option<Cell> {
+        match self {
+            Self::Classic(game) => Game::current_player(game),
+            Self::Numerical(game) => Game::current_player(game),
+        }
+    }
+
+    fn result(&self) -> GameResult {
+        match self {
+            Self::Classic(game) => game.result(),
+            Self::Numerical(game) => game.result(),
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<Move> {
+        match self {
+            Self::Classic(game) => game.legal_moves(),
+            Self::Numerical(game) => game.legal_moves(),
+        }
+    }
+
+    fn apply(&mut self, mv: &Move) -> bool {
+        match self {
+            Self::Classic(game) => game.apply(mv),
+            Self::Numerical(game) => game.apply(mv),
+        }
+    }
+
+    fn history(&self) -> &[Move] {
+        match self {
+            Self::Classic(game) => game.history(),
+            Self::Numerical(game) => game.history(),
+        }
+    }
+
+    fn winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        match self {
+            Self::Classic(game) => game.winning_line(),
+            Self::Numerical(game) => game.winning_line(),
+        }
+    }
+
+    fn play_turn(&mut self, moves: &[Move]) -> bool {
+        match self {
+            Self::Classic(game) => game.play_turn(moves),
+            Self::Numerical(game) => game.play_turn(moves),
+        }
+    }
+
+    fn hides_information(&self) -> bool {
+        match self {
+            Self::Classic(game) => game.hides_information(),
+            Self::Numerical(game) => game.hides_information(),
+        }
+    }
+
+    fn view_for(&self, viewer: Option<Cell>) -> Self {
+        match self {
+            Self::Classic(game) => Self::Classic(Game::view_for(game, viewer)),
+            Self::Numerical(game) => Self::Numerical(Game::view_for(game, viewer)),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+//! Rule engines for tic-tac-toe and its variants, free of any server code.
+
+#[cfg(feature = "serde")]
+pub mod api;
+pub mod classic;
+pub mod engine;
+pub mod game;
+pub mod notation;
+pub mod numerical;
+
+pub use classic::{Cell, Role, TicTacToeGame};
+pub use game::{AnyGame, Game, GameKind, GameResult, Move, MAX_SIZE};
+pub use numerical::NumericalGame;
@@ -0,0 +1,678 @@
+//! Text formats for sharing games.
+//!
+//! A *position* is a single line, much like FEN in chess:
+//!
+//! ```text
+//! X2/1O1/3 X 3 2.0 classic
+//! ```
+//!
+//! The fields are the board (rows separated by `/`, runs of empty cells as
+//! numbers), the player to move (`-` once the game is over), the criteria, the
+//! turn number and stones already placed in it, and the variant. Numerical
+//! boards list their cells separated by `,` with `.` for an empty cell, and
+//! carry the target sum instead of the criteria.
+//!
+//! A *game* is a header followed by the move list, much like PGN:
+//!
+//! ```text
+//! [Size "19"]
+//! [Criteria "6"]
+//! [Variant "stones=1,2"]
+//! [X "alice"]
+//! [O "bob"]
+//! [Result "*"]
+//!
+//! 1. 9,9 2. 8,8 8,9 3. 10,10 7,7
+//! ```
+//!
+//! Games that started from an imported position carry no history to replay.
+//! They are written with a `Position` header holding where they stand, and no
+//! moves.
+//!
+//! Moves are zero-based `row,column` pairs, with `=X`/`=O` for the chosen
+//! symbol in Order and Chaos or `=<number>` in numerical tic-tac-toe.
+//!
+//! The variant is `classic`, `numerical`, or any of `stones=<schedule>`,
+//! `rolling=<pieces>`, `phantom` and `order-chaos` joined with `+`.
+
+use std::fmt;
+
+use crate::{
+    classic::{Cell, Role, TicTacToeGame},
+    game::{AnyGame, Game, GameResult, Move, MAX_SIZE},
+    numerical::NumericalGame,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotationError(pub String);
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+fn error<T>(message: impl Into<String>) -> Result<T, NotationError> {
+    Err(NotationError(message.into()))
+}
+
+/// A game together with the players' names from its header
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    pub game: AnyGame,
+    pub x_player: Option<String>,
+    pub o_player: Option<String>,
+}
+
+pub fn write_variant(game: &AnyGame) -> String {
+    let game = match game {
+        AnyGame::Classic(game) => game,
+        AnyGame::Numerical(_) => return "numerical".to_owned(),
+    };
+    let mut parts = Vec::new();
+    if game.stones_per_turn != [1] {
+        let schedule: Vec<_> = game.stones_per_turn.iter().map(|n| n.to_string()).collect();
+        parts.push(format!("stones={}", schedule.join(",")));
+    }
+    if let Some(max) = game.max_pieces {
+        parts.push(format!("rolling={max}"));
+    }
+    if game.phantom {
+        parts.push("phantom".to_owned());
+    }
+    if game.order_and_chaos {
+        parts.push("order-chaos".to_owned());
+    }
+    if parts.is_empty() {
+        "classic".to_owned()
+    } else {
+        parts.join("+")
+    }
+}
+
+/// The starting position of a game with the given rules
+pub fn new_game(variant: &str, size: usize, criteria: usize) -> Result<AnyGame, NotationError> {
+    // Checked before anything is allocated for the board
+    if size > MAX_SIZE {
+        return error(format!("boards are at most {MAX_SIZE} cells wide"));
+    }
+    if variant == "numerical" {
+        return Ok(AnyGame::Numerical(NumericalGame::new(size)));
+    }
+    let mut game = TicTacToeGame::new(size, criteria);
+    for part in variant.split('+') {
+        match part.split_once('=') {
+            None if part == "classic" => {}
+            None if part == "phantom" => game.phantom = true,
+            None if part == "order-chaos" => game.order_and_chaos = true,
+            Some(("stones", schedule)) => {
+                game.stones_per_turn = schedule
+                    .split(',')
+                    .map(|n| parse_number(n, "stone count"))
+                    .collect::<Result<_, _>>()?;
+            }
+            Some(("rolling", max)) => game.max_pieces = Some(parse_number(max, "piece limit")?),
+            _ => return error(format!("unknown variant `{part}`")),
+        }
+    }
+    if !game.is_valid() {
+        return error(format!(
+            "invalid rules: {variant}, size {size}, criteria {criteria}"
+        ));
+    }
+    Ok(AnyGame::Classic(game))
+}
+
+fn parse_number<T: std::str::FromStr>(s: &str, what: &str) -> Result<T, NotationError> {
+    s.parse()
+        .or_else(|_| error(format!("expected a {what}, found `{s}`")))
+}
+
+fn criteria_of(game: &AnyGame) -> usize {
+    match game {
+        AnyGame::Classic(game) => game.criteria,
+        AnyGame::Numerical(game) => game.target as usize,
+    }
+}
+
+fn write_cell(cell: Cell) -> char {
+    match cell {
+        Cell::X => 'X',
+        Cell::O => 'O',
+    }
+}
+
+fn parse_cell(s: &str) -> Result<Cell, NotationError> {
+    match s {
+        "X" => Ok(Cell::X),
+        "O" => Ok(Cell::O),
+        _ => error(format!("expected X or O, found `{s}`")),
+    }
+}
+
+pub fn write_move(mv: &Move) -> String {
+    let mut s = format!("{},{}", mv.x, mv.y);
+    if let Some(number) = mv.number {
+        s += &format!("={number}");
+    } else if let Some(symbol) = mv.symbol {
+        s.push('=');
+        s.push(write_cell(symbol));
+    }
+    s
+}
+
+pub fn parse_move(s: &str) -> Result<Move, NotationError> {
+    let (coords, extra) = match s.split_once('=') {
+        Some((coords, extra)) => (coords, Some(extra)),
+        None => (s, None),
+    };
+    let (x, y) = coords
+        .split_once(',')
+        .ok_or_else(|| NotationError(format!("expected a `row,column` move, found `{s}`")))?;
+    let mut mv = Move::at(parse_number(x, "row")?, parse_number(y, "column")?);
+    match extra {
+        Some(symbol @ ("X" | "O")) => mv.symbol = Some(parse_cell(symbol)?),
+        Some(number) => mv.number = Some(parse_number(number, "number")?),
+        None => {}
+    }
+    Ok(mv)
+}
+
+pub fn write_position(game: &AnyGame) -> String {
+    let rows: Vec<String> = match game {
+        AnyGame::Classic(game) => game
+            .field
+            .iter()
+            .map(|row| {
+                let mut s = String::new();
+                let mut empty = 0;
+                for cell in row {
+                    match cell {
+                        Some(cell) => {
+                            if empty > 0 {
+                                s += &empty.to_string();
+                                empty = 0;
+                            }
+                            s.push(write_cell(*cell));
+                        }
+                        None => empty += 1,
+                    }
+                }
+                if empty > 0 {
+                    s += &empty.to_string();
+                }
+                s
+            })
+            .collect(),
+        AnyGame::Numerical(game) => game
+            .field
+            .iter()
+            .map(|row| {
+                let cells: Vec<_> = row
+                    .iter()
+                    .map(|cell| cell.map_or(".".to_owned(), |n| n.to_string()))
+                    .collect();
+                cells.join(",")
+            })
+            .collect(),
+    };
+    let to_move = match (game.result(), game.current_player()) {
+        (GameResult::Ongoing, Some(player)) => write_cell(player).to_string(),
+        _ => "-".to_owned(),
+    };
+    let counters = match game {
+        AnyGame::Classic(game) => format!("{}.{}", game.turn_number, game.stones_placed),
+        AnyGame::Numerical(_) => "-".to_owned(),
+    };
+    format!(
+        "{} {} {} {} {}",
+        rows.join("/"),
+        to_move,
+        criteria_of(game),
+        counters,
+        write_variant(game)
+    )
+}
+
+pub fn parse_position(s: &str) -> Result<AnyGame, NotationError> {
+    let fields: Vec<_> = s.split_whitespace().collect();
+    if fields.len() != 5 {
+        return error("a position has five fields: board, player, criteria, counters, variant");
+    }
+    let (board, to_move, criteria, counters, variant) =
+        (fields[0], fields[1], fields[2], fields[3], fields[4]);
+    let rows: Vec<_> = board.split('/').collect();
+    let size = rows.len();
+    let to_move = match to_move {
+        "-" => None,
+        player => Some(parse_cell(player)?),
+    };
+
+    let mut game = new_game(variant, size, parse_number(criteria, "criteria")?)?;
+    match &mut game {
+        AnyGame::Classic(game) => {
+            for (x, row) in rows.iter().enumerate() {
+                let mut y: usize = 0;
+                let mut chars = row.chars().peekable();
+                while let Some(c) = chars.next() {
+                    if let Some(digit) = c.to_digit(10) {
+                        let too_long =
+                            || NotationError(format!("row {x} is longer than {size} cells"));
+                        let mut empty = digit as usize;
+                        while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                            empty = empty
+                                .checked_mul(10)
+                                .and_then(|e| e.checked_add(digit as usize))
+                                .ok_or_else(too_long)?;
+                            chars.next();
+                        }
+                        y = y
+                            .checked_add(empty)
+                            .filter(|&y| y <= size)
+                            .ok_or_else(too_long)?;
+                        continue;
+                    }
+                    let cell = parse_cell(&c.to_string())?;
+                    if y >= size {
+                        return error(format!("row {x} is longer than {size} cells"));
+                    }
+                    // Positions don't record how old pieces are, so reading
+                    // order stands in for it
+                    game.field[x][y] = Some(cell);
+                    game.placed_at[x][y] = Some(game.move_count);
+                    game.move_count += 1;
+                    y += 1;
+                }
+                if y != size {
+                    return error(format!("row {x} has {y} cells instead of {size}"));
+                }
+            }
+            let (turn, placed) = counters.split_once('.').ok_or_else(|| {
+                NotationError(format!("expected `turn.stones`, found `{counters}`"))
+            })?;
+            game.turn_number = parse_number(turn, "turn number")?;
+            game.stones_placed = parse_number(placed, "stone count")?;
+            game.current_player = to_move.or(Some(Cell::X));
+            if let Some(winner) = game.check_field_for_winner() {
+                game.winner = Some(if game.order_and_chaos {
+                    Cell::X
+                } else {
+                    winner
+                });
+                game.current_player = Some(winner);
+            } else if game.check_draw() {
+                game.draw = true;
+            }
+            if game.order_and_chaos && game.draw {
+                game.draw = false;
+                game.winner = Some(Cell::O);
+            }
+            if let Some(winner) = game.winner {
+                game.winning_role = game.role_of(winner);
+                game.winning_line = game.find_winning_line();
+            }
+        }
+        AnyGame::Numerical(game) => {
+            for (x, row) in rows.iter().enumerate() {
+                let cells: Vec<_> = row.split(',').collect();
+                if cells.len() != size {
+                    return error(format!(
+                        "row {x} has {} cells instead of {size}",
+                        cells.len()
+                    ));
+                }
+                for (y, cell) in cells.into_iter().enumerate() {
+                    if cell == "." {
+                        continue;
+                    }
+                    let number: u32 = parse_number(cell, "number")?;
+                    let owner = if number % 2 == 1 { Cell::X } else { Cell::O };
+                    let numbers = match owner {
+                        Cell::X => &mut game.odd,
+                        Cell::O => &mut game.even,
+                    };
+                    let index = numbers
+                        .iter()
+                        .position(|&n| n == number)
+                        .ok_or_else(|| NotationError(format!("number {number} is used twice")))?;
+                    numbers.remove(index);
+                    game.field[x][y] = Some(number);
+                }
+            }
+            game.target = parse_number(criteria, "target")?;
+            // X moves first, so the number of filled cells tells who moved last
+            let filled = game.field.iter().flatten().filter(|c| c.is_some()).count();
+            let last_mover = if filled % 2 == 1 { Cell::X } else { Cell::O };
+            game.winning_line = game.find_winning_line();
+            if game.winning_line.is_some() {
+                game.winner = Some(last_mover);
+                game.current_player = Some(last_mover);
+            } else {
+                game.current_player = to_move.or(Some(last_mover.flip()));
+                game.draw = game.check_draw();
+            }
+        }
+    }
+    if to_move.is_none() && game.result() == GameResult::Ongoing {
+        return error("the game is marked as over, but nobody has won and the board is not full");
+    }
+    if to_move.is_some() && game.result() != GameResult::Ongoing {
+        return error("the game is marked as ongoing, but it is already over");
+    }
+    Ok(game)
+}
+
+fn write_result(result: GameResult) -> &'static str {
+    match result {
+        GameResult::Ongoing => "*",
+        GameResult::Won(Cell::X) => "X",
+        GameResult::Won(Cell::O) => "O",
+        GameResult::Draw => "draw",
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A fresh game with the same rules, ready to replay the history on
+pub fn restart(game: &AnyGame) -> AnyGame {
+    let criteria = match game {
+        AnyGame::Classic(game) => game.criteria,
+        AnyGame::Numerical(game) => game.size,
+    };
+    new_game(&write_variant(game), game.size(), criteria).expect("a running game has valid rules")
+}
+
+/// Whether replaying the history on [`restart`] leads to the game. Games
+/// imported from a position don't know how they got there.
+pub fn has_full_history(game: &AnyGame) -> bool {
+    let mut replay = restart(game);
+    game.history().iter().all(|mv| replay.apply(mv))
+        && write_position(&replay) == write_position(game)
+}
+
+pub fn write_game(record: &GameRecord) -> String {
+    let game = &record.game;
+    let mut s = String::new();
+    let mut header = |key: &str, value: &str| s += &format!("[{key} {}]\n", quote(value));
+    header("Size", &game.size().to_string());
+    if let AnyGame::Classic(game) = game {
+        header("Criteria", &game.criteria.to_string());
+    }
+    header("Variant", &write_variant(game));
+    if let Some(player) = &record.x_player {
+        header("X", player);
+    }
+    if let Some(player) = &record.o_player {
+        header("O", player);
+    }
+    let full_history = has_full_history(game);
+    if !full_history {
+        header("Position", &write_position(game));
+    }
+    header("Result", write_result(game.result()));
+    if let AnyGame::Classic(TicTacToeGame {
+        winning_role: Some(role),
+        ..
+    }) = game
+    {
+        header(
+            "Winner",
+            match role {
+                Role::Order => "Order",
+                Role::Chaos => "Chaos",
+            },
+        );
+    }
+    s.push('\n');
+
+    // Replay the game to find out where each turn starts
+    let mut replay = restart(game);
+    let mut turns: Vec<String> = Vec::new();
+    let mut mover = None;
+    let history = if full_history { game.history() } else { &[] };
+    for mv in history {
+        if replay.current_player() != mover || turns.is_empty() {
+            mover = replay.current_player();
+            turns.push(format!("{}.", turns.len() + 1));
+        }
+        let turn = turns.last_mut().expect("a turn was just started");
+        *turn += " ";
+        // The symbol is implied by the player unless the rules let them choose
+        let chooses_symbol = matches!(game, AnyGame::Classic(game) if game.order_and_chaos);
+        *turn += &write_move(&Move {
+            symbol: mv.symbol.filter(|_| chooses_symbol),
+            ..mv.clone()
+        });
+        replay.apply(mv);
+    }
+    s += &turns.join(" ");
+    s.push('\n');
+    s
+}
+
+fn parse_header(line: &str) -> Result<(String, String), NotationError> {
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .ok_or_else(|| NotationError(format!("malformed header `{line}`")))?;
+    let (key, value) = inner
+        .split_once(' ')
+        .ok_or_else(|| NotationError(format!("malformed header `{line}`")))?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(|| NotationError(format!("header value must be quoted in `{line}`")))?;
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            unescaped.extend(chars.next());
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Ok((key.to_owned(), unescaped))
+}
+
+pub fn parse_game(s: &str) -> Result<GameRecord, NotationError> {
+    let mut size = None;
+    let mut criteria = None;
+    let mut variant = "classic".to_owned();
+    let mut result = None;
+    let mut position = None;
+    let mut x_player = None;
+    let mut o_player = None;
+    let mut moves = String::new();
+    for line in s.lines().map(str::trim) {
+        if line.starts_with('[') {
+            let (key, value) = parse_header(line)?;
+            match key.as_str() {
+                "Size" => size = Some(parse_number(&value, "size")?),
+                "Criteria" => criteria = Some(parse_number(&value, "criteria")?),
+                "Variant" => variant = value,
+                "Result" => result = Some(value),
+                "Position" => position = Some(value),
+                "X" => x_player = Some(value),
+                "O" => o_player = Some(value),
+                // Unknown headers are kept for humans only
+                _ => {}
+            }
+        } else {
+            moves += line;
+            moves.push(' ');
+        }
+    }
+    let size = size.ok_or_else(|| NotationError("missing Size header".to_owned()))?;
+    let mut game = match position {
+        Some(position) => parse_position(&position)?,
+        None => new_game(&variant, size, criteria.unwrap_or(size))?,
+    };
+    if game.size() != size {
+        return error("the Size header does not match the position");
+    }
+    for token in moves.split_whitespace() {
+        if token.ends_with('.') && token[..token.len() - 1].chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let mv = parse_move(token)?;
+        if !game.apply(&mv) {
+            return error(format!("illegal move `{token}`"));
+        }
+    }
+    if let Some(result) = result {
+        if result != write_result(game.result()) {
+            return error(format!(
+                "the header says the result is `{result}`, but the moves give `{}`",
+                write_result(game.result())
+            ));
+        }
+    }
+    Ok(GameRecord {
+        game,
+        x_player,
+        o_player,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn classic(size: usize, criteria: usize) -> TicTacToeGame {
+        TicTacToeGame::new(size, criteria)
+    }
+
+    #[test]
+    fn position_round_trip() {
+        let mut game = classic(3, 3);
+        game.turn(0, 0);
+        game.turn(1, 1);
+        let game = AnyGame::Classic(game);
+        let position = write_position(&game);
+        assert_eq!("X2/1O1/3 X 3 2.0 classic", position);
+        assert_eq!(
+            position,
+            write_position(&parse_position(&position).unwrap())
+        );
+    }
+
+    #[test]
+    fn position_with_large_board() {
+        let mut game = classic(12, 5);
+        game.turn(0, 11);
+        let position = write_position(&AnyGame::Classic(game));
+        assert!(position.starts_with("11X/12/"), "{position}");
+        match parse_position(&position).unwrap() {
+            AnyGame::Classic(parsed) => assert_eq!(Some(Cell::X), parsed.field[0][11]),
+            _ => panic!("expected a classic game"),
+        }
+    }
+
+    #[test]
+    fn finished_position() {
+        let game = parse_position("XXX/OO1/3 - 3 3.0 classic").unwrap();
+        assert_eq!(GameResult::Won(Cell::X), game.result());
+        assert!(parse_position("XXX/OO1/3 O 3 3.0 classic").is_err());
+        assert!(parse_position("XX1/OO1/3 - 3 3.0 classic").is_err());
+    }
+
+    #[test]
+    fn numerical_position_round_trip() {
+        let mut game = NumericalGame::new(3);
+        game.turn(0, 0, 9);
+        game.turn(1, 1, 2);
+        let game = AnyGame::Numerical(game);
+        let position = write_position(&game);
+        assert_eq!("9,.,./.,2,./.,.,. X 15 - numerical", position);
+        let parsed = parse_position(&position).unwrap();
+        assert_eq!(position, write_position(&parsed));
+        match parsed {
+            AnyGame::Numerical(parsed) => assert_eq!(vec![1, 3, 5, 7], parsed.odd),
+            _ => panic!("expected a numerical game"),
+        }
+
+        let game = parse_position("9,1,5/.,2,./.,.,4 - 15 - numerical").unwrap();
+        assert_eq!(GameResult::Won(Cell::X), game.result());
+    }
+
+    #[test]
+    fn game_round_trip() {
+        let mut game = classic(19, 6);
+        game.stones_per_turn = vec![1, 2];
+        for (x, y) in [(9, 9), (8, 8), (8, 9), (10, 10), (7, 7)] {
+            game.turn(x, y);
+        }
+        let record = GameRecord {
+            game: AnyGame::Classic(game),
+            x_player: Some("alice".to_owned()),
+            o_player: Some("bob \"the builder\"".to_owned()),
+        };
+        let text = write_game(&record);
+        assert!(text.contains("[Variant \"stones=1,2\"]"), "{text}");
+        assert!(text.ends_with("1. 9,9 2. 8,8 8,9 3. 10,10 7,7\n"), "{text}");
+
+        let parsed = parse_game(&text).unwrap();
+        assert_eq!(record.o_player, parsed.o_player);
+        assert_eq!(text, write_game(&parsed));
+    }
+
+    #[test]
+    fn game_with_symbols_and_numbers() {
+        let mut game = classic(6, 5);
+        game.order_and_chaos = true;
+        game.turn_as(0, 0, Cell::O);
+        let text = write_game(&GameRecord {
+            game: AnyGame::Classic(game),
+            x_player: None,
+            o_player: None,
+        });
+        assert!(text.ends_with("1. 0,0=O\n"), "{text}");
+        assert!(parse_game(&text).is_ok());
+
+        let mut game = NumericalGame::new(3);
+        game.turn(1, 1, 5);
+        let text = write_game(&GameRecord {
+            game: AnyGame::Numerical(game),
+            x_player: None,
+            o_player: None,
+        });
+        assert!(text.ends_with("1. 1,1=5\n"), "{text}");
+        assert!(parse_game(&text).is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_games() {
+        assert!(parse_game("[Size \"3\"]\n1. 0,0 2. 0,0").is_err());
+        assert!(parse_game("[Size \"3\"]\n[Result \"X\"]\n1. 0,0").is_err());
+        assert!(parse_game("[Size \"3\"]\n[Variant \"chess\"]").is_err());
+        assert!(parse_game("1. 0,0").is_err());
+    }
+
+    #[test]
+    fn rejects_huge_boards_before_allocating() {
+        assert!(parse_game("[Size \"1000000\"]").is_err());
+        assert!(parse_game("[Size \"1000000\"]\n[Variant \"numerical\"]").is_err());
+        assert!(parse_position("99999999999999999999999/3/3 X 3 1.0 classic").is_err());
+        assert!(parse_position("18446744073709551615X/3/3 X 3 1.0 classic").is_err());
+    }
+
+    #[test]
+    fn imported_positions_round_trip_through_games() {
+        let game = parse_position("XXX/OO1/3 - 3 3.0 classic").unwrap();
+        assert_eq!(GameResult::Won(Cell::X), game.result());
+        let text = write_game(&GameRecord {
+            game: game.clone(),
+            x_player: None,
+            o_player: None,
+        });
+        assert!(text.contains("[Position "), "{text}");
+        let record = parse_game(&text).unwrap();
+        assert_eq!(write_position(&game), write_position(&record.game));
+        assert_eq!(GameResult::Won(Cell::X), record.game.result());
+    }
+}
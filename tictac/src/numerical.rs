@@ -0,0 +1,253 @@
+#[cfg(feature = "schemars")]
+use schemars::JsonSchema;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    classic::Cell,
+    game::{Game, GameResult, Move},
+};
+
+/// Numerical tic-tac-toe: X places odd numbers, O places even ones, each
+/// number once. Whoever completes a full line adding up to `target` wins.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct NumericalGame {
+    pub field: Vec<Vec<Option<u32>>>,
+    #[cfg_attr(feature = "serde", serde(rename = "currentPlayer"))]
+    pub current_player: Option<Cell>,
+    pub winner: Option<Cell>,
+    pub size: usize,
+    pub target: u32,
+    pub draw: bool,
+    /// Cells of the line that won the game
+    #[cfg_attr(feature = "serde", serde(rename = "winningLine"))]
+    pub winning_line: Option<Vec<(usize, usize)>>,
+    /// Numbers X has not placed yet
+    pub odd: Vec<u32>,
+    /// Numbers O has not placed yet
+    pub even: Vec<u32>,
+    pub history: Vec<Move>,
+}
+
+impl NumericalGame {
+    /// Uses the numbers `1..=size²` and the magic constant as the target,
+    /// which is 15 on the classic 3x3 board
+    pub fn new(size: usize) -> Self {
+        let max = (size * size) as u32;
+        Self {
+            field: vec![vec![None; size]; size],
+            current_player: Some(Cell::X),
+            winner: None,
+            size,
+            target: size as u32 * (max + 1) / 2,
+            draw: false,
+            winning_line: None,
+            odd: (1..=max).filter(|n| n % 2 == 1).collect(),
+            even: (1..=max).filter(|n| n % 2 == 0).collect(),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.size > 0
+    }
+
+    pub fn numbers_of(&self, player: Cell) -> &Vec<u32> {
+        match player {
+            Cell::X => &self.odd,
+            Cell::O => &self.even,
+        }
+    }
+
+    fn numbers_of_mut(&mut self, player: Cell) -> &mut Vec<u32> {
+        match player {
+            Cell::X => &mut self.odd,
+            Cell::O => &mut self.even,
+        }
+    }
+
+    pub fn turn(&mut self, x: usize, y: usize, number: u32) -> Option<Cell> {
+        if self.winner.is_some() || self.draw {
+            return None;
+        }
+        if self.field.get(x)?.get(y)?.is_some() {
+            return None;
+        }
+        let player = self.current_player?;
+        let numbers = self.numbers_of_mut(player);
+        let index = numbers.iter().position(|&n| n == number)?;
+        numbers.remove(index);
+        self.field[x][y] = Some(number);
+        self.history.push(Move {
+            number: Some(number),
+            ..Move::at(x, y)
+        });
+        self.winning_line = self.find_winning_line();
+        if self.winning_line.is_some() {
+            self.winner = Some(player);
+            return self.winner;
+        }
+        self.current_player = Some(player.flip());
+        self.draw = self.check_draw();
+        None
+    }
+
+    pub fn check_field_for_winner(&self) -> bool {
+        self.find_winning_line().is_some()
+    }
+
+    /// A full line adding up to the target, if there is one
+    pub fn find_winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        let n = self.size;
+        let mut lines: Vec<Vec<(usize, usize)>> = Vec::new();
+        for i in 0..n {
+            lines.push((0..n).map(|j| (i, j)).collect());
+            lines.push((0..n).map(|j| (j, i)).collect());
+        }
+        lines.push((0..n).map(|i| (i, i)).collect());
+        lines.push((0..n).map(|i| (i, n - 1 - i)).collect());
+        lines
+            .into_iter()
+            .find(|line| self.line_sum(line) == Some(self.target))
+    }
+
+    /// Sum of a line, or `None` while any of its cells is empty
+    fn line_sum(&self, line: &[(usize, usize)]) -> Option<u32> {
+        line.iter().map(|&(x, y)| self.field[x][y]).sum()
+    }
+
+    pub(crate) fn check_draw(&self) -> bool {
+        let board_full = self.field.iter().all(|x| x.iter().all(|y| y.is_some()));
+        let out_of_numbers = match self.current_player {
+            Some(player) => self.numbers_of(player).is_empty(),
+            None => true,
+        };
+        board_full || out_of_numbers
+    }
+}
+
+impl Game for NumericalGame {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn is_valid(&self) -> bool {
+        NumericalGame::is_valid(self)
+    }
+
+    fn current_player(&self) -> Option<Cell> {
+        self.current_player
+    }
+
+    fn result(&self) -> GameResult {
+        match self.winner {
+            Some(winner) => GameResult::Won(winner),
+            None if self.draw => GameResult::Draw,
+            None => GameResult::Ongoing,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let numbers = match (self.result(), self.current_player) {
+            (GameResult::Ongoing, Some(player)) => self.numbers_of(player),
+            _ => return moves,
+        };
+        for (x, row) in self.field.iter().enumerate() {
+            for (y, cell) in row.iter().enumerate() {
+                if cell.is_none() {
+                    for &number in numbers {
+                        moves.push(Move {
+                            number: Some(number),
+                            ..Move::at(x, y)
+                        });
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    fn winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        self.winning_line.clone()
+    }
+
+    fn apply(&mut self, mv: &Move) -> bool {
+        let before = self.odd.len() + self.even.len();
+        if let Some(number) = mv.number {
+            self.turn(mv.x, mv.y, number);
+        }
+        self.odd.len() + self.even.len() != before
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::NumericalGame;
+    use crate::{
+        classic::Cell::*,
+        game::{Game, Move},
+    };
+
+    #[test]
+    fn classic_board_sums_to_fifteen() {
+        let x = NumericalGame::new(3);
+        assert_eq!(15, x.target);
+        assert_eq!(vec![1, 3, 5, 7, 9], x.odd);
+        assert_eq!(vec![2, 4, 6, 8], x.even);
+    }
+
+    #[test]
+    fn rejects_foreign_and_used_numbers() {
+        let mut x = NumericalGame::new(3);
+        x.turn(0, 0, 2);
+        assert_eq!(None, x.field[0][0]);
+        x.turn(0, 0, 1);
+        assert_eq!(Some(O), x.current_player);
+        x.turn(0, 1, 1);
+        assert_eq!(None, x.field[0][1]);
+    }
+
+    #[test]
+    fn completing_a_line_wins() {
+        let mut x = NumericalGame::new(3);
+        x.turn(0, 0, 9);
+        x.turn(1, 1, 2);
+        assert_eq!(None, x.winner);
+        x.turn(0, 1, 1);
+        x.turn(2, 2, 6);
+        assert_eq!(None, x.winner);
+        // 9 + 1 + 5 on the top row
+        assert_eq!(Some(X), x.turn(0, 2, 5));
+        assert_eq!(Some(X), x.winner);
+        assert_eq!(Some(vec![(0, 0), (0, 1), (0, 2)]), x.winning_line);
+    }
+
+    #[test]
+    fn full_line_with_other_sum_does_not_win() {
+        let mut x = NumericalGame::new(3);
+        x.turn(0, 0, 1);
+        x.turn(0, 1, 2);
+        x.turn(0, 2, 3);
+        assert_eq!(None, x.winner);
+        assert_eq!(Some(O), x.current_player);
+    }
+
+    #[test]
+    fn legal_moves_use_own_numbers() {
+        let mut x = NumericalGame::new(3);
+        assert_eq!(9 * 5, x.legal_moves().len());
+        assert!(!x.apply(&Move::at(0, 0)));
+        assert!(x.apply(&Move {
+            number: Some(5),
+            ..Move::at(0, 0)
+        }));
+        assert_eq!(8 * 4, x.legal_moves().len());
+    }
+}
@@ -0,0 +1,52 @@
+use tictac::{AnyGame, Game, GameResult, NumericalGame, TicTacToeGame};
+
+/// Plays the first legal move until the game is over
+fn play_out(game: &mut impl Game) {
+    while game.result() == GameResult::Ongoing {
+        let moves = game.legal_moves();
+        assert!(!moves.is_empty(), "ongoing game without legal moves");
+        assert!(game.apply(&moves[0]));
+    }
+    assert!(game.legal_moves().is_empty());
+}
+
+#[test]
+fn classic_games_finish() {
+    for size in 1..7 {
+        for criteria in 1..=size {
+            let mut game = AnyGame::Classic(TicTacToeGame::new(size, criteria));
+            play_out(&mut game);
+        }
+    }
+}
+
+#[test]
+fn connect6_games_finish() {
+    let mut game = TicTacToeGame::new(10, 6);
+    game.stones_per_turn = vec![1, 2];
+    play_out(&mut game);
+    assert_ne!(GameResult::Ongoing, game.result());
+}
+
+#[test]
+fn numerical_games_finish() {
+    for size in 1..5 {
+        let mut game = AnyGame::Numerical(NumericalGame::new(size));
+        play_out(&mut game);
+    }
+}
+
+#[test]
+fn history_replays_to_the_same_board() {
+    let mut game = TicTacToeGame::new(5, 4);
+    game.stones_per_turn = vec![1, 2];
+    play_out(&mut game);
+
+    let mut replay = TicTacToeGame::new(5, 4);
+    replay.stones_per_turn = vec![1, 2];
+    for mv in game.history() {
+        assert!(replay.apply(mv));
+    }
+    assert_eq!(game.field, replay.field);
+    assert_eq!(game.result(), replay.result());
+}
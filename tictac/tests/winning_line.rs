@@ -0,0 +1,93 @@
+use proptest::prelude::*;
+use tictac::{Game, GameResult, NumericalGame, TicTacToeGame};
+
+/// Plays the game out, using `choices` to pick among the legal moves
+fn play_out(game: &mut impl Game, choices: &[usize]) {
+    for &choice in choices {
+        let moves = game.legal_moves();
+        if moves.is_empty() {
+            break;
+        }
+        assert!(game.apply(&moves[choice % moves.len()]));
+    }
+}
+
+/// `line` has `len` cells, each one step further in the same direction
+fn is_straight(line: &[(usize, usize)], len: usize) -> bool {
+    if line.len() != len {
+        return false;
+    }
+    let step = |a: (usize, usize), b: (usize, usize)| {
+        (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize)
+    };
+    let first = match line {
+        [a, b, ..] => step(*a, *b),
+        _ => return true,
+    };
+    [(0, 1), (1, 0), (1, 1), (1, -1)].contains(&first)
+        && line.windows(2).all(|w| step(w[0], w[1]) == first)
+}
+
+proptest! {
+    #[test]
+    fn classic_line_matches_board(
+        (size, criteria) in (1usize..7).prop_flat_map(|size| (Just(size), 1..=size)),
+        stones in 1usize..3,
+        choices in prop::collection::vec(any::<usize>(), 0..50),
+    ) {
+        let mut game = TicTacToeGame::new(size, criteria);
+        game.stones_per_turn = vec![1, stones];
+        play_out(&mut game, &choices);
+        match (game.result(), game.winning_line()) {
+            (GameResult::Won(winner), Some(line)) => {
+                prop_assert!(is_straight(&line, criteria), "{:?}", line);
+                for (x, y) in line {
+                    prop_assert_eq!(Some(winner), game.field[x][y]);
+                }
+            }
+            (GameResult::Won(_), None) => prop_assert!(false, "won without a line"),
+            (_, line) => prop_assert_eq!(None, line),
+        }
+    }
+
+    #[test]
+    fn order_and_chaos_line_matches_board(
+        choices in prop::collection::vec(any::<usize>(), 0..40),
+    ) {
+        let mut game = TicTacToeGame::new(4, 3);
+        game.order_and_chaos = true;
+        play_out(&mut game, &choices);
+        match game.winning_line() {
+            Some(line) => {
+                prop_assert_eq!(GameResult::Won(tictac::Cell::X), game.result());
+                prop_assert!(is_straight(&line, 3), "{:?}", line);
+                let (x, y) = line[0];
+                let symbol = game.field[x][y];
+                prop_assert!(symbol.is_some());
+                for (x, y) in line {
+                    prop_assert_eq!(symbol, game.field[x][y]);
+                }
+            }
+            // Chaos only wins by filling the board
+            None => prop_assert_ne!(GameResult::Won(tictac::Cell::X), game.result()),
+        }
+    }
+
+    #[test]
+    fn numerical_line_matches_board(
+        size in 1usize..5,
+        choices in prop::collection::vec(any::<usize>(), 0..20),
+    ) {
+        let mut game = NumericalGame::new(size);
+        play_out(&mut game, &choices);
+        match (game.result(), game.winning_line()) {
+            (GameResult::Won(_), Some(line)) => {
+                prop_assert!(is_straight(&line, size), "{:?}", line);
+                let sum: Option<u32> = line.iter().map(|&(x, y)| game.field[x][y]).sum();
+                prop_assert_eq!(Some(game.target), sum);
+            }
+            (GameResult::Won(_), None) => prop_assert!(false, "won without a line"),
+            (_, line) => prop_assert_eq!(None, line),
+        }
+    }
+}